@@ -0,0 +1,567 @@
+use std::collections::BTreeSet;
+use std::f64::consts::PI;
+
+use crate::config::calculator::CalculatorSettings;
+
+/// An ender eye thrown from `x`, `z`, flying off at `angle`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Throw {
+    pub x: f64,
+    pub z: f64,
+    /// The horizontal angle from F3, in Minecraft's convention of degrees
+    /// clockwise from south.
+    pub angle: f64,
+    /// Degrees to add to `angle` from the eye's pixel offset in tall mode,
+    /// if it was measured.
+    pub correction: Option<f64>,
+}
+
+impl Throw {
+    fn corrected_angle(&self) -> f64 {
+        self.angle + self.correction.unwrap_or(0.0)
+    }
+
+    fn sigma(&self, settings: &CalculatorSettings) -> f64 {
+        match self.correction {
+            Some(_) => settings.corrected_sigma,
+            None => settings.sigma,
+        }
+    }
+
+    /// The angle at which an eye thrown here would fly toward `(x, z)`.
+    fn angle_to(&self, x: f64, z: f64) -> f64 {
+        (-(x - self.x)).atan2(z - self.z).to_degrees()
+    }
+}
+
+/// A ring of strongholds around the origin, with distances in chunks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ring {
+    pub count: u32,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// How far strongholds may be moved from where they're placed, in chunks,
+/// to find a suitable biome.
+const BIOME_SNAP: f64 = 7.0;
+
+/// Rings of the 128 strongholds. Each is placed `128 + 192 * ring` chunks
+/// from the origin give or take 40, at evenly spaced angles from a random
+/// start, then moved a little to find a suitable biome.
+pub const RINGS: [Ring; 8] = {
+    const COUNTS: [u32; 8] = [3, 6, 10, 15, 21, 28, 36, 9];
+
+    let mut rings = [Ring {
+        count: 0,
+        min: 0.0,
+        max: 0.0,
+    }; 8];
+    let mut i = 0;
+    while i < rings.len() {
+        let center = 128.0 + 192.0 * i as f64;
+        rings[i] = Ring {
+            count: COUNTS[i],
+            min: center - 40.0 - BIOME_SNAP,
+            max: center + 40.0 + BIOME_SNAP,
+        };
+        i += 1;
+    }
+    rings
+};
+
+/// Where in its chunk a stronghold's eyes lead, in blocks from the chunk's
+/// corner. The middle of the chunk up to 1.18.
+const EYE_TARGET: f64 = 8.0;
+
+/// Widest the search around the first throw gets, in its standard
+/// deviations.
+const SEARCH_SIGMAS: f64 = 5.0;
+
+/// A chunk a stronghold may be in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidate {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub probability: f64,
+    /// Blocks from the last throw.
+    pub distance: f64,
+}
+
+impl Candidate {
+    /// The block eyes lead to in the overworld.
+    pub fn overworld(&self) -> (i32, i32) {
+        (
+            self.chunk_x * 16 + EYE_TARGET as i32,
+            self.chunk_z * 16 + EYE_TARGET as i32,
+        )
+    }
+
+    /// The block of the nether that leads there.
+    pub fn nether(&self) -> (i32, i32) {
+        let (x, z) = self.overworld();
+        (x.div_euclid(8), z.div_euclid(8))
+    }
+}
+
+/// The prior probability of a stronghold in the chunk at `(x, z)`. Distances
+/// are uniform within a ring, so the density falls off as one over the
+/// distance from the origin, and angles are uniform.
+pub fn prior(chunk_x: i32, chunk_z: i32) -> f64 {
+    let distance = (chunk_x as f64).hypot(chunk_z as f64);
+
+    RINGS
+        .iter()
+        .filter(|ring| (ring.min..ring.max).contains(&distance))
+        .map(|ring| ring.count as f64 / (2.0 * PI * distance * (ring.max - ring.min)))
+        .sum()
+}
+
+/// Distance between the circles [`closer_counts`] integrates over, in chunks.
+const NEAREST_STEP: f64 = 4.0;
+/// Points sampled around each of those circles.
+const NEAREST_SAMPLES: usize = 64;
+
+/// The expected number of strongholds within `i * NEAREST_STEP` chunks of
+/// the chunk at `(x, z)`, for each `i` up to `reach`. Eyes lead to the
+/// nearest stronghold, so a candidate is only as likely as there being none
+/// closer.
+fn closer_counts(x: f64, z: f64, reach: f64) -> Vec<f64> {
+    let mut counts = vec![0.0];
+    let mut radius = 0.0;
+
+    while radius < reach {
+        let middle = radius + NEAREST_STEP / 2.0;
+        let density = (0..NEAREST_SAMPLES)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f64 / NEAREST_SAMPLES as f64;
+                prior(
+                    (x + middle * angle.cos()).round() as i32,
+                    (z + middle * angle.sin()).round() as i32,
+                )
+            })
+            .sum::<f64>()
+            / NEAREST_SAMPLES as f64;
+
+        let count = counts[counts.len() - 1] + density * 2.0 * PI * middle * NEAREST_STEP;
+        counts.push(count);
+        radius += NEAREST_STEP;
+    }
+
+    counts
+}
+
+/// Wraps an angle difference into -180 to 180 degrees.
+fn wrap(angle: f64) -> f64 {
+    (angle + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Chunks within reach of the first throw's line that could hold a
+/// stronghold.
+fn search_area(throw: &Throw, settings: &CalculatorSettings) -> BTreeSet<(i32, i32)> {
+    let spread = (throw.sigma(settings) * SEARCH_SIGMAS).to_radians().tan();
+    let angle = throw.corrected_angle().to_radians();
+    let (dx, dz) = (-angle.sin(), angle.cos());
+    let reach = throw.x.hypot(throw.z) + RINGS[RINGS.len() - 1].max * 16.0;
+
+    let mut chunks = BTreeSet::new();
+    let mut t = 0.0;
+    while t < reach {
+        let width = t * spread + 16.0;
+        let mut side = -width;
+        while side <= width {
+            let x = throw.x + dx * t - dz * side;
+            let z = throw.z + dz * t + dx * side;
+            let chunk = (
+                ((x - EYE_TARGET) / 16.0).round() as i32,
+                ((z - EYE_TARGET) / 16.0).round() as i32,
+            );
+            if prior(chunk.0, chunk.1) > 0.0 {
+                chunks.insert(chunk);
+            }
+            side += 8.0;
+        }
+        t += 8.0;
+    }
+
+    chunks
+}
+
+/// Weighs every chunk near the throws by how well it explains them against
+/// where strongholds generate, returning the likeliest first.
+pub fn triangulate(throws: &[Throw], settings: &CalculatorSettings) -> Vec<Candidate> {
+    let (Some(first), Some(last)) = (throws.first(), throws.last()) else {
+        return Vec::new();
+    };
+
+    let origin = (first.x / 16.0, first.z / 16.0);
+    let reach = origin.0.hypot(origin.1) + RINGS[RINGS.len() - 1].max;
+    let closer = closer_counts(origin.0, origin.1, reach);
+
+    let mut candidates: Vec<(i32, i32, f64)> = search_area(first, settings)
+        .into_iter()
+        .map(|(chunk_x, chunk_z)| {
+            let x = chunk_x as f64 * 16.0 + EYE_TARGET;
+            let z = chunk_z as f64 * 16.0 + EYE_TARGET;
+            let distance = (chunk_x as f64 - origin.0).hypot(chunk_z as f64 - origin.1);
+            let step = ((distance / NEAREST_STEP) as usize).min(closer.len() - 1);
+            let log_likelihood: f64 = throws
+                .iter()
+                .map(|throw| {
+                    let error = wrap(throw.corrected_angle() - throw.angle_to(x, z));
+                    -0.5 * (error / throw.sigma(settings)).powi(2)
+                })
+                .sum();
+
+            (
+                chunk_x,
+                chunk_z,
+                prior(chunk_x, chunk_z).ln() - closer[step] + log_likelihood,
+            )
+        })
+        .collect();
+
+    // Normalized against the best, so the exponents can't all underflow.
+    let best = candidates
+        .iter()
+        .map(|&(.., log_posterior)| log_posterior)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let total: f64 = candidates
+        .iter()
+        .map(|&(.., log_posterior)| (log_posterior - best).exp())
+        .sum();
+
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+    candidates
+        .into_iter()
+        .take(settings.candidates)
+        .map(|(chunk_x, chunk_z, log_posterior)| {
+            let x = chunk_x as f64 * 16.0 + EYE_TARGET;
+            let z = chunk_z as f64 * 16.0 + EYE_TARGET;
+            Candidate {
+                chunk_x,
+                chunk_z,
+                probability: (log_posterior - best).exp() / total,
+                distance: (x - last.x).hypot(z - last.z),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A throw from `(x, z)` toward the stronghold in `chunk`, with the
+    /// angle rounded to the two decimals F3 shows.
+    fn throw_toward(x: f64, z: f64, chunk: (i32, i32)) -> Throw {
+        let throw = Throw {
+            x,
+            z,
+            angle: 0.0,
+            correction: None,
+        };
+        let angle = throw.angle_to(
+            chunk.0 as f64 * 16.0 + EYE_TARGET,
+            chunk.1 as f64 * 16.0 + EYE_TARGET,
+        );
+
+        Throw {
+            angle: (angle * 100.0).round() / 100.0,
+            ..throw
+        }
+    }
+
+    /// `java.util.Random`, which the game places strongholds with.
+    struct JavaRandom(u64);
+
+    impl JavaRandom {
+        const MULTIPLIER: u64 = 0x5DEECE66D;
+        const MASK: u64 = (1 << 48) - 1;
+
+        fn new(seed: i64) -> Self {
+            Self((seed as u64 ^ Self::MULTIPLIER) & Self::MASK)
+        }
+
+        fn next(&mut self, bits: u32) -> i32 {
+            self.0 = self.0.wrapping_mul(Self::MULTIPLIER).wrapping_add(0xB) & Self::MASK;
+            (self.0 >> (48 - bits)) as u32 as i32
+        }
+
+        fn next_double(&mut self) -> f64 {
+            let bits = ((self.next(26) as i64) << 27) + self.next(27) as i64;
+            bits as f64 / (1u64 << 53) as f64
+        }
+
+        fn next_long(&mut self) -> i64 {
+            ((self.next(32) as i64) << 32).wrapping_add(self.next(32) as i64)
+        }
+    }
+
+    /// The ring and chunk of each stronghold of `seed` before it's moved to
+    /// a suitable biome, placed the way the game does since 1.19.
+    fn placements(seed: i64) -> Vec<(usize, i32, i32)> {
+        let mut random = JavaRandom::new(seed);
+        let mut angle = random.next_double() * 2.0 * PI;
+        let (mut ring, mut placed, mut spread) = (0, 0, 3);
+
+        let mut strongholds = Vec::new();
+        for i in 0..128 {
+            let distance = (128 + 192 * ring) as f64 + (random.next_double() - 0.5) * 80.0;
+            strongholds.push((
+                ring,
+                (angle.cos() * distance + 0.5).floor() as i32,
+                (angle.sin() * distance + 0.5).floor() as i32,
+            ));
+            // The biome search gets a random of its own.
+            random.next_long();
+
+            angle += 2.0 * PI / spread as f64;
+            placed += 1;
+            if placed == spread {
+                ring += 1;
+                placed = 0;
+                spread += 2 * spread / (ring + 1);
+                spread = spread.min(128 - i - 1);
+                angle += random.next_double() * 2.0 * PI;
+            }
+        }
+
+        strongholds
+    }
+
+    const SEEDS: [i64; 4] = [0, 1, 1234567890, -4172144997902289642];
+
+    #[test]
+    fn java_random_matches_the_jdk() {
+        let mut random = JavaRandom::new(0);
+        assert_eq!(random.next(32), -1155484576);
+        assert_eq!(random.next(32), -723955400);
+
+        let mut random = JavaRandom::new(42);
+        assert_eq!(random.next_double(), 0.7275636800328681);
+        assert_eq!(random.next_long(), -5843495416241995736);
+    }
+
+    #[test]
+    fn rings_hold_what_seeds_place() {
+        for seed in SEEDS {
+            let strongholds = placements(seed);
+
+            for (i, ring) in RINGS.iter().enumerate() {
+                let placed: Vec<_> = strongholds.iter().filter(|(r, ..)| *r == i).collect();
+                assert_eq!(placed.len(), ring.count as usize, "ring {} of {}", i, seed);
+
+                for &&(_, x, z) in &placed {
+                    let distance = (x as f64).hypot(z as f64);
+                    // Moving to a biome takes strongholds up to the edges.
+                    assert!(
+                        (ring.min + BIOME_SNAP - 1.0..ring.max - BIOME_SNAP + 1.0)
+                            .contains(&distance),
+                        "{} is {} chunks out, outside ring {}",
+                        seed,
+                        distance,
+                        i
+                    );
+                    assert!(prior(x, z) > 0.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn two_throws_find_the_strongholds_of_seeds() {
+        for seed in SEEDS {
+            for &(_, x, z) in placements(seed).iter().take(3) {
+                // Thrown a little outside the first ring, so this is the
+                // nearest stronghold.
+                let (dx, dz) = (x as f64 / 2.0, z as f64 / 2.0);
+                let throws = [
+                    throw_toward(dx * 16.0 - dz * 4.0, dz * 16.0 + dx * 4.0, (x, z)),
+                    throw_toward(dx * 16.0 + dz * 8.0, dz * 16.0 - dx * 8.0, (x, z)),
+                ];
+                let candidates = triangulate(&throws, &CalculatorSettings::default());
+
+                let best = candidates[0];
+                assert!(
+                    (best.chunk_x - x).abs() <= 1 && (best.chunk_z - z).abs() <= 1,
+                    "{}: {:?} instead of {:?}",
+                    seed,
+                    candidates,
+                    (x, z)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn angles_follow_minecraft_yaw() {
+        let throw = Throw {
+            x: 0.0,
+            z: 0.0,
+            angle: 0.0,
+            correction: None,
+        };
+
+        assert!(throw.angle_to(0.0, 100.0).abs() < 1e-9);
+        assert!((throw.angle_to(-100.0, 0.0) - 90.0).abs() < 1e-9);
+        assert!((throw.angle_to(100.0, 0.0) + 90.0).abs() < 1e-9);
+        assert!((throw.angle_to(0.0, -100.0).abs() - 180.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn wraps_angle_differences() {
+        assert_eq!(wrap(0.0), 0.0);
+        assert_eq!(wrap(190.0), -170.0);
+        assert_eq!(wrap(-190.0), 170.0);
+        assert_eq!(wrap(360.0), 0.0);
+        assert_eq!(wrap(-720.5), -0.5);
+    }
+
+    #[test]
+    fn nether_coordinates_round_down() {
+        let candidate = |chunk_x, chunk_z| Candidate {
+            chunk_x,
+            chunk_z,
+            probability: 1.0,
+            distance: 0.0,
+        };
+
+        assert_eq!(candidate(100, -20).overworld(), (1608, -312));
+        assert_eq!(candidate(100, -20).nether(), (201, -39));
+        assert_eq!(candidate(-1, -1).nether(), (-1, -1));
+        assert_eq!(candidate(-2, 0).nether(), (-3, 1));
+    }
+
+    #[test]
+    fn prior_is_zero_between_rings() {
+        assert_eq!(prior(0, 0), 0.0);
+        assert_eq!(prior(60, 0), 0.0);
+        assert_eq!(prior(250, 0), 0.0);
+        assert!(prior(128, 0) > 0.0);
+        assert!(prior(0, -320) > 0.0);
+    }
+
+    #[test]
+    fn prior_adds_up_to_each_ring() {
+        for (i, ring) in RINGS.iter().enumerate().take(3) {
+            let reach = ring.max.ceil() as i32;
+            let expected: f64 = (-reach..=reach)
+                .flat_map(|x| (-reach..=reach).map(move |z| (x, z)))
+                .filter(|&(x, z)| {
+                    let distance = (x as f64).hypot(z as f64);
+                    (ring.min..ring.max).contains(&distance)
+                })
+                .map(|(x, z)| prior(x, z))
+                .sum();
+
+            assert!(
+                (expected - ring.count as f64).abs() < ring.count as f64 * 0.02,
+                "ring {} holds {} strongholds, not {}",
+                i,
+                expected,
+                ring.count
+            );
+        }
+    }
+
+    #[test]
+    fn no_throws_no_candidates() {
+        assert!(triangulate(&[], &CalculatorSettings::default()).is_empty());
+    }
+
+    #[test]
+    fn one_throw_points_along_its_line_in_the_nearest_ring() {
+        let throw = throw_toward(200.0, 300.0, (100, -20));
+        let candidates = triangulate(&[throw], &CalculatorSettings::default());
+
+        assert_eq!(candidates.len(), 5);
+        for pair in candidates.windows(2) {
+            assert!(pair[0].probability >= pair[1].probability);
+        }
+        for candidate in &candidates {
+            let (x, z) = candidate.overworld();
+            let error = wrap(throw.angle - throw.angle_to(x as f64, z as f64));
+            assert!(error.abs() < 0.5, "{:?} is off the line", candidate);
+
+            let distance = (candidate.chunk_x as f64).hypot(candidate.chunk_z as f64);
+            assert!((RINGS[0].min..RINGS[0].max).contains(&distance));
+        }
+    }
+
+    #[test]
+    fn two_throws_find_the_stronghold() {
+        for (chunk, first, second) in [
+            ((100, -20), (200.0, 300.0), (600.0, -900.0)),
+            ((-37, 118), (-40.0, 25.0), (-700.0, 600.0)),
+            ((-300, -120), (-150.0, -80.0), (-600.0, -1800.0)),
+        ] {
+            let throws = [
+                throw_toward(first.0, first.1, chunk),
+                throw_toward(second.0, second.1, chunk),
+            ];
+            let candidates = triangulate(&throws, &CalculatorSettings::default());
+
+            // F3's two decimals can't always tell neighbouring chunks apart
+            // from thousands of blocks away.
+            let near = |candidate: &Candidate| {
+                (candidate.chunk_x - chunk.0).abs() <= 1 && (candidate.chunk_z - chunk.1).abs() <= 1
+            };
+            assert!(near(&candidates[0]), "{:?}", candidates);
+            assert!(
+                candidates
+                    .iter()
+                    .any(|candidate| (candidate.chunk_x, candidate.chunk_z) == chunk)
+            );
+            let nearby: f64 = candidates
+                .iter()
+                .filter(|candidate| near(candidate))
+                .map(|candidate| candidate.probability)
+                .sum();
+            assert!(nearby > 0.5, "{:?}", candidates);
+
+            let best = candidates[0];
+            let (x, z) = best.overworld();
+            let distance = (x as f64 - second.0).hypot(z as f64 - second.1);
+            assert!((best.distance - distance).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn corrected_throws_narrow_down_far_rings() {
+        let chunk = (690, 130);
+        let throws = [
+            throw_toward(2000.0, 900.0, chunk),
+            throw_toward(1700.0, 2900.0, chunk),
+        ]
+        .map(|throw| Throw {
+            correction: Some(0.0),
+            ..throw
+        });
+        let candidates = triangulate(&throws, &CalculatorSettings::default());
+
+        let best = candidates[0];
+        assert!(
+            (best.chunk_x - chunk.0).abs() <= 3 && (best.chunk_z - chunk.1).abs() <= 3,
+            "{:?}",
+            candidates
+        );
+    }
+
+    #[test]
+    fn corrections_are_added_to_the_angle() {
+        let chunk = (100, -20);
+        let throws = [
+            throw_toward(200.0, 300.0, chunk),
+            throw_toward(600.0, -900.0, chunk),
+        ]
+        .map(|throw| Throw {
+            angle: throw.angle - 0.25,
+            correction: Some(0.25),
+            ..throw
+        });
+        let candidates = triangulate(&throws, &CalculatorSettings::default());
+
+        assert_eq!((candidates[0].chunk_x, candidates[0].chunk_z), chunk);
+    }
+}
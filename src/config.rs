@@ -1,8 +1,18 @@
+pub mod calculator;
 pub mod color;
+pub mod detection;
+pub mod hooks;
+pub mod log_pattern;
+pub mod macros;
+pub mod mouse;
+pub mod per_mode;
+pub mod projector;
 pub mod xy;
 
 use std::{
+    fmt::Display,
     fs::{File, create_dir_all},
+    path::PathBuf,
     sync::LazyLock,
 };
 
@@ -15,22 +25,139 @@ use serde::{Deserialize, Serialize};
 use std::io::Write;
 
 use crate::{
-    config::xy::XY,
+    config::{
+        calculator::CalculatorSettings,
+        detection::DetectionRules,
+        hooks::ModeHooks,
+        log_pattern::LogPattern,
+        macros::{Macro, ModeMacros},
+        mouse::MouseSettings,
+        per_mode::PerMode,
+        projector::{Backend, ProjectorSettings},
+        xy::XY,
+    },
     keylogger::{KeyFilter, Modifiers},
+    mode::Transition,
 };
 
-static PROJECT_DIR: LazyLock<ProjectDirs> =
+pub static PROJECT_DIR: LazyLock<ProjectDirs> =
     LazyLock::new(|| ProjectDirs::from("", "", "wrinkle").unwrap());
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Hotkey {
     Thin,
     Tall,
     Wide,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+impl Hotkey {
+    pub const ALL: [Hotkey; 3] = [Hotkey::Tall, Hotkey::Thin, Hotkey::Wide];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Hotkey::Thin => "thin",
+            Hotkey::Tall => "tall",
+            Hotkey::Wide => "wide",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|hotkey| hotkey.name() == name)
+    }
+}
+
+impl Display for Hotkey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Hotkey::Thin => f.write_str("Thin"),
+            Hotkey::Tall => f.write_str("Tall"),
+            Hotkey::Wide => f.write_str("Wide"),
+        }
+    }
+}
+
+/// Anything a key can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Mode(Hotkey),
+    Back,
+    Reset,
+    /// The macro at this index in `Config::macros`.
+    Macro(usize),
+}
+
+impl Binding {
+    pub const ALL: [Binding; 5] = [
+        Binding::Mode(Hotkey::Tall),
+        Binding::Mode(Hotkey::Thin),
+        Binding::Mode(Hotkey::Wide),
+        Binding::Back,
+        Binding::Reset,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Binding::Mode(hotkey) => hotkey.name(),
+            Binding::Back => "back",
+            Binding::Reset => "reset",
+            Binding::Macro(_) => "macro",
+        }
+    }
+}
+
+/// Geometry the instance is given outside of any resize mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Normal {
+    /// Cover the whole monitor the instance is on.
+    #[default]
+    Borderless,
+    /// Keep the rect the instance had when it was first found.
+    Original,
+    Custom {
+        position: XY,
+        size: XY,
+    },
+}
+
+/// When instance windows have their frame removed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Borderless {
+    #[default]
+    Always,
+    /// Only while in a resize mode, keeping the frame in normal.
+    InMode,
+    Never,
+}
+
+impl Borderless {
+    pub const ALL: [Borderless; 3] = [Borderless::Always, Borderless::InMode, Borderless::Never];
+
+    pub fn applies(self, mode: Option<Hotkey>) -> bool {
+        match self {
+            Borderless::Always => true,
+            Borderless::InMode => mode.is_some(),
+            Borderless::Never => false,
+        }
+    }
+}
+
+impl Display for Borderless {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Borderless::Always => f.write_str("Always"),
+            Borderless::InMode => f.write_str("In mode"),
+            Borderless::Never => f.write_str("Never"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Config {
+    pub normal: Normal,
+    pub borderless: Borderless,
     pub thin: xy::XY,
     pub tall: xy::XY,
     pub wide: xy::XY,
@@ -38,12 +165,37 @@ pub struct Config {
     pub thin_key: Option<KeyFilter>,
     pub tall_key: Option<KeyFilter>,
     pub wide_key: Option<KeyFilter>,
+    pub back_key: Option<KeyFilter>,
+    pub reset_key: Option<KeyFilter>,
+    pub transition: Transition,
+    pub mouse: PerMode<MouseSettings>,
+    pub hooks_enabled: bool,
+    pub hooks: PerMode<ModeHooks>,
+    pub macros: Vec<Macro>,
+    pub mode_macros: PerMode<ModeMacros>,
+    pub detection: DetectionRules,
+    /// Game directory to read `wpstateout.txt` from for instances whose own
+    /// can't be found from their command line.
+    pub game_dir: Option<PathBuf>,
+    /// Ignore mode keys while the game reports being outside of a world.
+    pub require_in_world: bool,
+    /// Go back to normal when the game reports leaving the world.
+    pub reset_outside_world: bool,
+    /// Extra `latest.log` line formats, tried after the built-in ones.
+    pub log_patterns: Vec<LogPattern>,
+    pub projectors: Vec<ProjectorSettings>,
+    /// Rate projectors are repainted at while shown.
+    pub projector_fps: u32,
+    pub projector_backend: Backend,
+    pub calculator: CalculatorSettings,
     pub colors: [color::Color; 2],
 }
 
 impl Default for Config {
     fn default() -> Config {
         Self {
+            normal: Normal::default(),
+            borderless: Borderless::default(),
             tall: xy::XY::new(384, 16384),
             thin: xy::XY::new(400, 1800),
             wide: xy::XY::new(1920, 300),
@@ -66,6 +218,29 @@ impl Default for Config {
                     ..Modifiers::default()
                 }),
             }),
+            back_key: None,
+            reset_key: None,
+            transition: Transition::default(),
+            mouse: PerMode {
+                tall: MouseSettings {
+                    speed: Some(1),
+                    enhance_pointer_precision: Some(false),
+                },
+                ..PerMode::default()
+            },
+            hooks_enabled: true,
+            hooks: PerMode::default(),
+            macros: Vec::new(),
+            mode_macros: PerMode::default(),
+            detection: DetectionRules::default(),
+            game_dir: None,
+            require_in_world: false,
+            reset_outside_world: false,
+            log_patterns: Vec::new(),
+            projectors: projector::default_projectors(),
+            projector_fps: 60,
+            projector_backend: Backend::default(),
+            calculator: CalculatorSettings::default(),
             colors: [color::Color(91, 207, 250), color::Color(245, 171, 185)],
         }
     }
@@ -77,10 +252,11 @@ impl Config {
         let buf = config_dir.join("config.toml");
         let config_file = buf.to_str().unwrap();
 
-        let config = Figment::from(Serialized::defaults(Config::default()))
+        let mut config: Config = Figment::from(Serialized::defaults(Config::default()))
             .merge(Toml::file(config_file))
             .extract()
             .unwrap();
+        config.calculator = config.calculator.validated();
 
         config
     }
@@ -98,17 +274,28 @@ impl Config {
         Ok(())
     }
 
-    pub fn set_hotkey(mut self, hotkey: Hotkey, key_filter: Option<KeyFilter>) -> Self {
-        match hotkey {
-            Hotkey::Thin => {
+    pub fn set_key(mut self, binding: Binding, key_filter: Option<KeyFilter>) -> Self {
+        match binding {
+            Binding::Mode(Hotkey::Thin) => {
                 self.thin_key = key_filter;
             }
-            Hotkey::Tall => {
+            Binding::Mode(Hotkey::Tall) => {
                 self.tall_key = key_filter;
             }
-            Hotkey::Wide => {
+            Binding::Mode(Hotkey::Wide) => {
                 self.wide_key = key_filter;
             }
+            Binding::Back => {
+                self.back_key = key_filter;
+            }
+            Binding::Reset => {
+                self.reset_key = key_filter;
+            }
+            Binding::Macro(i) => {
+                if let Some(m) = self.macros.get_mut(i) {
+                    m.key = key_filter;
+                }
+            }
         }
         self
     }
@@ -128,11 +315,24 @@ impl Config {
         self
     }
 
-    pub fn get_hotkey(&self, hotkey: Hotkey) -> Option<KeyFilter> {
-        match hotkey {
-            Hotkey::Thin => self.thin_key,
-            Hotkey::Tall => self.tall_key,
-            Hotkey::Wide => self.wide_key,
+    pub fn get_key(&self, binding: Binding) -> Option<KeyFilter> {
+        match binding {
+            Binding::Mode(Hotkey::Thin) => self.thin_key,
+            Binding::Mode(Hotkey::Tall) => self.tall_key,
+            Binding::Mode(Hotkey::Wide) => self.wide_key,
+            Binding::Back => self.back_key,
+            Binding::Reset => self.reset_key,
+            Binding::Macro(i) => self.macros.get(i).and_then(|m| m.key),
         }
     }
+
+    pub fn find_projector(&self, name: &str) -> Option<&ProjectorSettings> {
+        self.projectors
+            .iter()
+            .find(|projector| projector.name == name)
+    }
+
+    pub fn find_macro(&self, name: &str) -> Option<&Macro> {
+        self.macros.iter().find(|m| m.name == name)
+    }
 }
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// Settings of the stronghold calculator.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalculatorSettings {
+    /// Standard deviation of a throw's angle in degrees, as read from F3.
+    pub sigma: f64,
+    /// Standard deviation of a throw's angle once corrected by the pixel
+    /// offset of the eye, measured in tall mode.
+    pub corrected_sigma: f64,
+    /// How many of the likeliest chunks to list.
+    pub candidates: usize,
+}
+
+impl Default for CalculatorSettings {
+    fn default() -> Self {
+        Self {
+            sigma: 0.1,
+            corrected_sigma: 0.03,
+            candidates: 5,
+        }
+    }
+}
+
+impl CalculatorSettings {
+    /// Puts back the default of each standard deviation that isn't a
+    /// positive number, as it would make every probability NaN.
+    pub fn validated(self) -> Self {
+        let default = Self::default();
+        let valid = |name: &str, sigma: f64, default: f64| {
+            if sigma.is_finite() && sigma > 0.0 {
+                sigma
+            } else {
+                log::warn!(
+                    "Invalid calculator {}: {}, using {} instead",
+                    name,
+                    sigma,
+                    default
+                );
+                default
+            }
+        };
+
+        Self {
+            sigma: valid("sigma", self.sigma, default.sigma),
+            corrected_sigma: valid(
+                "corrected_sigma",
+                self.corrected_sigma,
+                default.corrected_sigma,
+            ),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_positive_sigmas() {
+        let settings = CalculatorSettings {
+            sigma: 0.2,
+            corrected_sigma: 0.01,
+            candidates: 3,
+        };
+        assert_eq!(settings.validated(), settings);
+    }
+
+    #[test]
+    fn replaces_sigmas_that_arent_positive() {
+        for sigma in [0.0, -0.1, f64::NAN, f64::INFINITY] {
+            let settings = CalculatorSettings {
+                sigma,
+                corrected_sigma: sigma,
+                candidates: 3,
+            }
+            .validated();
+
+            assert_eq!(
+                settings,
+                CalculatorSettings {
+                    candidates: 3,
+                    ..CalculatorSettings::default()
+                }
+            );
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt::Display;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// What a window is matched against.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WindowInfo {
+    pub title: String,
+    pub class: String,
+    /// File name of the process executable, such as `javaw.exe`.
+    pub executable: Option<String>,
+    pub command_line: Option<String>,
+}
+
+/// Matches a window when every condition it sets holds. A rule without
+/// conditions matches every window.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rule {
+    /// Regex the window title must match.
+    pub title: Option<String>,
+    /// Regex the window class must match.
+    pub class: Option<String>,
+    /// Executable file names, any of which the process may have. Case
+    /// insensitive.
+    pub executable: Vec<String>,
+    /// Fragments that must all appear in the process command line.
+    pub command_line: Vec<String>,
+}
+
+/// Why a window was or wasn't taken for a Minecraft instance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// Matched the include rule at this index, and no exclude rule.
+    Included(usize),
+    /// Matched the include rule and then the exclude rule at these indices.
+    Excluded(usize, usize),
+    /// Matched no include rule, with the reason each one failed.
+    Unmatched(Vec<String>),
+}
+
+impl Verdict {
+    pub fn is_match(&self) -> bool {
+        matches!(self, Verdict::Included(_))
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Included(i) => write!(f, "matches include rule {}", i + 1),
+            Verdict::Excluded(i, j) => write!(
+                f,
+                "matches include rule {} but also exclude rule {}",
+                i + 1,
+                j + 1
+            ),
+            Verdict::Unmatched(reasons) if reasons.is_empty() => f.write_str("no include rules"),
+            Verdict::Unmatched(reasons) => f.write_str(&reasons.join("; ")),
+        }
+    }
+}
+
+/// Which windows are Minecraft instances: those matching any `include` rule
+/// and no `exclude` rule.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DetectionRules {
+    pub include: Vec<Rule>,
+    pub exclude: Vec<Rule>,
+}
+
+impl Default for DetectionRules {
+    fn default() -> Self {
+        Self {
+            include: vec![Rule {
+                title: Some(r"^Minecraft\*?( |$)".to_string()),
+                class: Some("^GLFW30$".to_string()),
+                executable: vec!["javaw.exe".to_string(), "java.exe".to_string()],
+                ..Rule::default()
+            }],
+            exclude: Vec::new(),
+        }
+    }
+}
+
+/// A [`Rule`] with its regexes compiled. A regex that doesn't compile fails
+/// every window, with the error as the reason.
+#[derive(Clone, Debug)]
+struct CompiledRule {
+    title: Option<Result<Regex, String>>,
+    class: Option<Result<Regex, String>>,
+    executable: Vec<String>,
+    command_line: Vec<String>,
+}
+
+impl CompiledRule {
+    fn new(rule: &Rule) -> Self {
+        let regex = |field: &str, pattern: &Option<String>| {
+            pattern.as_ref().map(|pattern| {
+                Regex::new(pattern).map_err(|e| format!("bad {} regex: {}", field, e))
+            })
+        };
+
+        Self {
+            title: regex("title", &rule.title),
+            class: regex("class", &rule.class),
+            executable: rule.executable.clone(),
+            command_line: rule.command_line.clone(),
+        }
+    }
+
+    /// Whether the rule looks at the window's process, which takes opening
+    /// it to find out about.
+    fn needs_process(&self) -> bool {
+        !self.executable.is_empty() || !self.command_line.is_empty()
+    }
+
+    /// Checks the title and class of `window`, giving the first condition
+    /// that fails.
+    fn test_window(&self, window: &WindowInfo) -> Result<(), String> {
+        let regex = |field: &str, regex: &Option<Result<Regex, String>>, value: &str| match regex {
+            None => Ok(()),
+            Some(Err(e)) => Err(e.clone()),
+            Some(Ok(regex)) if regex.is_match(value) => Ok(()),
+            Some(Ok(regex)) => Err(format!(
+                "{} {:?} doesn't match {:?}",
+                field,
+                value,
+                regex.as_str()
+            )),
+        };
+
+        regex("title", &self.title, &window.title)?;
+        regex("class", &self.class, &window.class)
+    }
+
+    /// Checks the executable and command line of `window`, giving the first
+    /// condition that fails.
+    fn test_process(&self, window: &WindowInfo) -> Result<(), String> {
+        if !self.executable.is_empty() {
+            let Some(executable) = &window.executable else {
+                return Err("executable unknown".to_string());
+            };
+            if !self
+                .executable
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(executable))
+            {
+                return Err(format!(
+                    "executable {} not in {:?}",
+                    executable, self.executable
+                ));
+            }
+        }
+
+        if !self.command_line.is_empty() {
+            let Some(command_line) = &window.command_line else {
+                return Err("command line unknown".to_string());
+            };
+            if let Some(fragment) = self
+                .command_line
+                .iter()
+                .find(|fragment| !command_line.contains(fragment.as_str()))
+            {
+                return Err(format!("command line lacks {:?}", fragment));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// [`DetectionRules`] ready to judge windows, compiled once per config load.
+#[derive(Clone, Debug)]
+pub struct Detector {
+    include: Vec<CompiledRule>,
+    exclude: Vec<CompiledRule>,
+}
+
+impl Detector {
+    pub fn new(rules: &DetectionRules) -> Self {
+        Self {
+            include: rules.include.iter().map(CompiledRule::new).collect(),
+            exclude: rules.exclude.iter().map(CompiledRule::new).collect(),
+        }
+    }
+
+    /// Judges `window` by the title and class it comes with first. `resolve`
+    /// fills in its executable and command line the first time a rule gets
+    /// far enough to need them.
+    pub fn judge(&self, window: &mut WindowInfo, resolve: impl FnOnce(&mut WindowInfo)) -> Verdict {
+        let mut resolve = Some(resolve);
+        let mut test = |rule: &CompiledRule, window: &mut WindowInfo| {
+            rule.test_window(window)?;
+            if rule.needs_process()
+                && let Some(resolve) = resolve.take()
+            {
+                resolve(window);
+            }
+            rule.test_process(window)
+        };
+
+        let mut reasons = Vec::new();
+
+        for (i, rule) in self.include.iter().enumerate() {
+            match test(rule, window) {
+                Ok(()) => {
+                    return match self
+                        .exclude
+                        .iter()
+                        .position(|rule| test(rule, window).is_ok())
+                    {
+                        Some(j) => Verdict::Excluded(i, j),
+                        None => Verdict::Included(i),
+                    };
+                }
+                Err(reason) => reasons.push(reason),
+            }
+        }
+
+        Verdict::Unmatched(reasons)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(title: &str, class: &str) -> WindowInfo {
+        WindowInfo {
+            title: title.to_string(),
+            class: class.to_string(),
+            ..WindowInfo::default()
+        }
+    }
+
+    /// Judges `window`, returning the verdict and whether its process was
+    /// looked up.
+    fn judge(rules: &DetectionRules, mut window: WindowInfo) -> (Verdict, bool) {
+        let mut resolved = false;
+        let verdict = Detector::new(rules).judge(&mut window, |window| {
+            resolved = true;
+            window.executable = Some("javaw.exe".to_string());
+            window.command_line = Some("javaw -cp x.jar org.prismlauncher.EntryPoint".to_string());
+        });
+        (verdict, resolved)
+    }
+
+    #[test]
+    fn matches_minecraft_by_default() {
+        let rules = DetectionRules::default();
+
+        assert_eq!(
+            judge(&rules, window("Minecraft* 1.16.1", "GLFW30")),
+            (Verdict::Included(0), true)
+        );
+    }
+
+    #[test]
+    fn skips_the_process_when_title_or_class_fail() {
+        let rules = DetectionRules::default();
+
+        let (verdict, resolved) = judge(&rules, window("Notepad", "GLFW30"));
+        assert!(!verdict.is_match());
+        assert!(!resolved);
+
+        let (verdict, resolved) = judge(&rules, window("Minecraft 1.16.1", "Chrome_WidgetWin_1"));
+        assert!(!verdict.is_match());
+        assert!(!resolved);
+    }
+
+    #[test]
+    fn skips_the_process_when_no_rule_needs_it() {
+        let rules = DetectionRules {
+            include: vec![Rule {
+                class: Some("^GLFW30$".to_string()),
+                ..Rule::default()
+            }],
+            exclude: Vec::new(),
+        };
+
+        assert_eq!(
+            judge(&rules, window("Minecraft", "GLFW30")),
+            (Verdict::Included(0), false)
+        );
+    }
+
+    #[test]
+    fn exclude_rules_see_the_process() {
+        let rules = DetectionRules {
+            include: DetectionRules::default().include,
+            exclude: vec![Rule {
+                command_line: vec!["prismlauncher".to_string()],
+                ..Rule::default()
+            }],
+        };
+
+        assert_eq!(
+            judge(&rules, window("Minecraft 1.16.1", "GLFW30")),
+            (Verdict::Excluded(0, 0), true)
+        );
+    }
+
+    #[test]
+    fn reasons_are_given_per_rule() {
+        let rules = DetectionRules {
+            include: vec![
+                Rule {
+                    title: Some("(".to_string()),
+                    ..Rule::default()
+                },
+                Rule {
+                    executable: vec!["java.exe".to_string()],
+                    ..Rule::default()
+                },
+            ],
+            exclude: Vec::new(),
+        };
+
+        let (verdict, _) = judge(&rules, window("Minecraft", "GLFW30"));
+        let Verdict::Unmatched(reasons) = verdict else {
+            panic!("{:?}", verdict);
+        };
+        assert!(reasons[0].starts_with("bad title regex"), "{}", reasons[0]);
+        assert_eq!(reasons[1], r#"executable javaw.exe not in ["java.exe"]"#);
+    }
+}
@@ -0,0 +1,30 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// An executable run when a mode is entered or left.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookCommand {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Milliseconds after which the command is killed.
+    #[serde(default = "HookCommand::default_timeout")]
+    pub timeout: u64,
+}
+
+impl HookCommand {
+    fn default_timeout() -> u64 {
+        10_000
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModeHooks {
+    #[serde(default)]
+    pub on_enter: Vec<HookCommand>,
+    #[serde(default)]
+    pub on_exit: Vec<HookCommand>,
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// A user-defined log line format, for messages mods write to `latest.log`.
+/// Lines matching `regex` are reported as an event named `name`, along with
+/// the regex's capture groups.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogPattern {
+    pub name: String,
+    pub regex: String,
+}
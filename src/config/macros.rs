@@ -0,0 +1,157 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::keylogger::KeyFilter;
+
+/// Scan codes of the keys macros can press, as `(name, scan code, extended)`.
+const KEYS: &[(&str, u16, bool)] = &[
+    ("escape", 0x01, false),
+    ("1", 0x02, false),
+    ("2", 0x03, false),
+    ("3", 0x04, false),
+    ("4", 0x05, false),
+    ("5", 0x06, false),
+    ("6", 0x07, false),
+    ("7", 0x08, false),
+    ("8", 0x09, false),
+    ("9", 0x0A, false),
+    ("0", 0x0B, false),
+    ("backspace", 0x0E, false),
+    ("tab", 0x0F, false),
+    ("q", 0x10, false),
+    ("w", 0x11, false),
+    ("e", 0x12, false),
+    ("r", 0x13, false),
+    ("t", 0x14, false),
+    ("y", 0x15, false),
+    ("u", 0x16, false),
+    ("i", 0x17, false),
+    ("o", 0x18, false),
+    ("p", 0x19, false),
+    ("enter", 0x1C, false),
+    ("ctrl", 0x1D, false),
+    ("a", 0x1E, false),
+    ("s", 0x1F, false),
+    ("d", 0x20, false),
+    ("f", 0x21, false),
+    ("g", 0x22, false),
+    ("h", 0x23, false),
+    ("j", 0x24, false),
+    ("k", 0x25, false),
+    ("l", 0x26, false),
+    ("shift", 0x2A, false),
+    ("z", 0x2C, false),
+    ("x", 0x2D, false),
+    ("c", 0x2E, false),
+    ("v", 0x2F, false),
+    ("b", 0x30, false),
+    ("n", 0x31, false),
+    ("m", 0x32, false),
+    ("right_shift", 0x36, false),
+    ("alt", 0x38, false),
+    ("space", 0x39, false),
+    ("f1", 0x3B, false),
+    ("f2", 0x3C, false),
+    ("f3", 0x3D, false),
+    ("f4", 0x3E, false),
+    ("f5", 0x3F, false),
+    ("f6", 0x40, false),
+    ("f7", 0x41, false),
+    ("f8", 0x42, false),
+    ("f9", 0x43, false),
+    ("f10", 0x44, false),
+    ("f11", 0x57, false),
+    ("f12", 0x58, false),
+    ("home", 0x47, true),
+    ("up", 0x48, true),
+    ("page_up", 0x49, true),
+    ("left", 0x4B, true),
+    ("right", 0x4D, true),
+    ("end", 0x4F, true),
+    ("down", 0x50, true),
+    ("page_down", 0x51, true),
+    ("insert", 0x52, true),
+    ("delete", 0x53, true),
+];
+
+#[derive(Error, Debug)]
+#[error("unknown key: {0}")]
+pub struct UnknownKeyError(String);
+
+/// A physical key, identified by its scan code so games that read scan codes
+/// see the same key regardless of keyboard layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Key {
+    pub scan_code: u16,
+    pub extended: bool,
+}
+
+impl Key {
+    pub fn name(self) -> &'static str {
+        KEYS.iter()
+            .find(|&&(_, scan_code, extended)| {
+                (scan_code, extended) == (self.scan_code, self.extended)
+            })
+            .map(|&(name, _, _)| name)
+            .unwrap_or("unknown")
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = UnknownKeyError;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        let lowercase = name.to_lowercase();
+
+        KEYS.iter()
+            .find(|(key, _, _)| *key == lowercase)
+            .map(|&(_, scan_code, extended)| Key {
+                scan_code,
+                extended,
+            })
+            .ok_or(UnknownKeyError(name))
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        key.name().to_string()
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MacroStep {
+    /// Press and release a key.
+    Tap(Key),
+    /// Hold a key down until a matching `release`, or the end of the macro.
+    Press(Key),
+    Release(Key),
+    /// Wait for a number of milliseconds.
+    Delay(u64),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Macro {
+    pub name: String,
+    pub key: Option<KeyFilter>,
+    pub steps: Vec<MacroStep>,
+}
+
+/// Macros played when a mode is entered or left, by name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModeMacros {
+    #[serde(default)]
+    pub on_enter: Vec<String>,
+    #[serde(default)]
+    pub on_exit: Vec<String>,
+}
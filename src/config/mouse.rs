@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// Pointer settings applied while a mode is active. Unset fields leave the
+/// user's own setting alone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MouseSettings {
+    /// Windows pointer speed, from 1 to 20.
+    pub speed: Option<i32>,
+    /// "Enhance pointer precision" in the mouse control panel.
+    pub enhance_pointer_precision: Option<bool>,
+}
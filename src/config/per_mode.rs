@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::Hotkey;
+
+/// One value for each resize mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PerMode<T> {
+    pub thin: T,
+    pub tall: T,
+    pub wide: T,
+}
+
+impl<T> PerMode<T> {
+    pub fn get(&self, hotkey: Hotkey) -> &T {
+        match hotkey {
+            Hotkey::Thin => &self.thin,
+            Hotkey::Tall => &self.tall,
+            Hotkey::Wide => &self.wide,
+        }
+    }
+
+    pub fn get_mut(&mut self, hotkey: Hotkey) -> &mut T {
+        match hotkey {
+            Hotkey::Thin => &mut self.thin,
+            Hotkey::Tall => &mut self.tall,
+            Hotkey::Wide => &mut self.wide,
+        }
+    }
+
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> PerMode<U> {
+        PerMode {
+            thin: f(self.thin),
+            tall: f(self.tall),
+            wide: f(self.wide),
+        }
+    }
+}
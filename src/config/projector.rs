@@ -0,0 +1,373 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Hotkey, color::Color, xy::XY};
+
+/// A point of the game window that source rects are placed against.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    pub const ALL: [Anchor; 9] = [
+        Anchor::TopLeft,
+        Anchor::Top,
+        Anchor::TopRight,
+        Anchor::Left,
+        Anchor::Center,
+        Anchor::Right,
+        Anchor::BottomLeft,
+        Anchor::Bottom,
+        Anchor::BottomRight,
+    ];
+
+    /// The anchor's point within a rect of `size`.
+    pub fn point(self, size: XY) -> XY {
+        let (x, y) = match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        };
+
+        XY::new(size.x * x / 2, size.y * y / 2)
+    }
+}
+
+impl Display for Anchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Anchor::TopLeft => f.write_str("Top left"),
+            Anchor::Top => f.write_str("Top"),
+            Anchor::TopRight => f.write_str("Top right"),
+            Anchor::Left => f.write_str("Left"),
+            Anchor::Center => f.write_str("Center"),
+            Anchor::Right => f.write_str("Right"),
+            Anchor::BottomLeft => f.write_str("Bottom left"),
+            Anchor::Bottom => f.write_str("Bottom"),
+            Anchor::BottomRight => f.write_str("Bottom right"),
+        }
+    }
+}
+
+/// Where in the game window a blit copies from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// A rect of `size` whose own `anchor` point sits `offset` away from the
+    /// window's, so a centered rect stays centered whatever the window size.
+    Rect {
+        anchor: Anchor,
+        offset: XY,
+        size: XY,
+    },
+    /// The F3 pie chart, found from the game's GUI scale.
+    Pie,
+    /// The F3 entity counter, found from the game's GUI scale.
+    Entities,
+}
+
+/// How the source is fitted into the destination.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scaling {
+    /// Fill the destination, distorting the source if needed.
+    #[default]
+    Stretch,
+    /// The largest size with the source's aspect ratio, in the top left of
+    /// the destination.
+    Fit,
+    /// The largest whole multiple of the source's size, centered in the
+    /// destination with the rest left black.
+    Integer,
+}
+
+impl Scaling {
+    pub const ALL: [Scaling; 3] = [Scaling::Stretch, Scaling::Fit, Scaling::Integer];
+}
+
+impl Display for Scaling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scaling::Stretch => f.write_str("Stretch"),
+            Scaling::Fit => f.write_str("Fit"),
+            Scaling::Integer => f.write_str("Integer"),
+        }
+    }
+}
+
+/// How pixels are sampled when the source is resized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    /// Copy the closest source pixel, keeping edges crisp for measuring.
+    #[default]
+    Nearest,
+    /// Blend neighbouring pixels, for a smoother picture.
+    Smooth,
+}
+
+impl Filter {
+    pub const ALL: [Filter; 2] = [Filter::Nearest, Filter::Smooth];
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Filter::Nearest => f.write_str("Nearest"),
+            Filter::Smooth => f.write_str("Smooth"),
+        }
+    }
+}
+
+/// What draws the projectors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// GDI stretches straight from the game window.
+    #[default]
+    Gdi,
+    /// The regions are copied out of the game window and scaled in memory.
+    Software,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 2] = [Backend::Gdi, Backend::Software];
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Gdi => f.write_str("GDI"),
+            Backend::Software => f.write_str("Software"),
+        }
+    }
+}
+
+/// A rect within the projector, in fractions of its size.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Destination {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Destination {
+    pub const FULL: Destination = Destination {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+}
+
+impl Display for Destination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, {}, {}, {}", self.x, self.y, self.width, self.height)
+    }
+}
+
+/// One copy from the game window to the projector.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Blit {
+    pub source: Source,
+    pub destination: Destination,
+    #[serde(default)]
+    pub scaling: Scaling,
+    #[serde(default)]
+    pub filter: Filter,
+}
+
+/// Which side of the game window a projector goes on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    #[default]
+    Left,
+    Right,
+}
+
+impl Side {
+    pub const ALL: [Side; 2] = [Side::Left, Side::Right];
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Left => f.write_str("Left"),
+            Side::Right => f.write_str("Right"),
+        }
+    }
+}
+
+/// Where a projector window goes on its monitor. Placements beside the game
+/// window take the whole width of monitors the game isn't on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Placement {
+    /// Fill the space between the game window and the edge of the monitor,
+    /// `height` tall and centered vertically.
+    Beside { side: Side, height: i32 },
+    /// Fill the whole space between the game window and the edge of the
+    /// monitor.
+    Fill { side: Side },
+    /// At `position` from the top left corner of the monitor.
+    Custom { position: XY, size: XY },
+}
+
+/// Picks out a few colors of a projector's picture, such as the pie chart
+/// wedges of spawners or chests, by muting everything else.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Isolation {
+    pub enabled: bool,
+    /// Colors to keep.
+    pub colors: Vec<Color>,
+    /// How far each channel of a pixel may be from a kept color and still
+    /// count as it.
+    pub tolerance: u8,
+    /// Percentage of their brightness other pixels keep.
+    pub dim: u8,
+    /// Turn other pixels grey.
+    pub desaturate: bool,
+    /// Contrast of kept pixels in percent, 100 leaving them as they are.
+    pub contrast: u16,
+}
+
+impl Default for Isolation {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            colors: Vec::new(),
+            tolerance: 8,
+            dim: 25,
+            desaturate: true,
+            contrast: 100,
+        }
+    }
+}
+
+/// A projector window and what it shows.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectorSettings {
+    /// Identifies the window across config changes, so it must be unique.
+    pub name: String,
+    /// Modes the projector is shown in.
+    pub modes: Vec<Hotkey>,
+    /// Index of the monitor to place the projector on, in the order Windows
+    /// enumerates them. The monitor of the game window if unset, or if
+    /// there's no such monitor.
+    #[serde(default)]
+    pub monitor: Option<usize>,
+    pub placement: Placement,
+    #[serde(default)]
+    pub topmost: bool,
+    /// Draw the measuring ruler over the projector.
+    #[serde(default)]
+    pub ruler: bool,
+    #[serde(default)]
+    pub blits: Vec<Blit>,
+    /// Post-processing of the picture, drawn in software when enabled.
+    #[serde(default)]
+    pub isolation: Isolation,
+    /// Where to write out the pie chart read from the game, in large text.
+    #[serde(default)]
+    pub readout: Option<Destination>,
+    /// Look for a thrown ender eye in the source of the first blit, which
+    /// should be centered, and publish how far it is from the crosshair.
+    #[serde(default)]
+    pub detect_eye: bool,
+}
+
+impl ProjectorSettings {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            modes: Vec::new(),
+            monitor: None,
+            placement: Placement::Beside {
+                side: Side::Left,
+                height: 800,
+            },
+            topmost: true,
+            ruler: false,
+            blits: Vec::new(),
+            isolation: Isolation::default(),
+            readout: None,
+            detect_eye: false,
+        }
+    }
+
+    pub fn shown_in(&self, mode: Option<Hotkey>) -> bool {
+        mode.is_some_and(|mode| self.modes.contains(&mode)) && !self.blits.is_empty()
+    }
+}
+
+pub fn default_projectors() -> Vec<ProjectorSettings> {
+    vec![
+        ProjectorSettings {
+            modes: vec![Hotkey::Tall],
+            ruler: true,
+            detect_eye: true,
+            blits: vec![Blit {
+                source: Source::Rect {
+                    anchor: Anchor::Center,
+                    offset: XY::new(0, 0),
+                    size: XY::new(60, 500),
+                },
+                destination: Destination::FULL,
+                scaling: Scaling::Stretch,
+                filter: Filter::Nearest,
+            }],
+            ..ProjectorSettings::new("eye".to_string())
+        },
+        ProjectorSettings {
+            modes: vec![Hotkey::Thin],
+            placement: Placement::Beside {
+                side: Side::Left,
+                height: 1400,
+            },
+            blits: vec![
+                Blit {
+                    source: Source::Entities,
+                    destination: Destination {
+                        height: 0.1,
+                        ..Destination::FULL
+                    },
+                    scaling: Scaling::Fit,
+                    filter: Filter::Nearest,
+                },
+                Blit {
+                    source: Source::Pie,
+                    destination: Destination {
+                        x: 0.0,
+                        y: 0.1,
+                        width: 1.0,
+                        height: 0.9,
+                    },
+                    scaling: Scaling::Stretch,
+                    filter: Filter::Smooth,
+                },
+            ],
+            ..ProjectorSettings::new("pie".to_string())
+        },
+    ]
+}
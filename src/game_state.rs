@@ -0,0 +1,295 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+use futures::StreamExt;
+use futures_channel::mpsc;
+use thiserror::Error;
+use windows::Win32::Foundation::HWND;
+
+use crate::{
+    config::Config,
+    instance::registry::{self, InstanceRegistry},
+    watch,
+};
+
+/// Name of the file WorldPreview and similar mods keep the game state in,
+/// inside the instance's game directory.
+const STATE_FILE: &str = "wpstateout.txt";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InWorld {
+    Unpaused,
+    Paused,
+    /// A menu such as the inventory or a chest is open.
+    GameScreenOpen,
+}
+
+/// What the game is doing, as reported in `wpstateout.txt`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameState {
+    /// There is no state file, either because no mod writes it or because the
+    /// game directory isn't known.
+    #[default]
+    Unknown,
+    Title,
+    Waiting,
+    /// Generating a world, with the percentage done.
+    Generating(u8),
+    /// Showing the world preview, with the percentage done.
+    Previewing(u8),
+    InWorld(InWorld),
+    Wall,
+}
+
+impl GameState {
+    pub fn in_world(self) -> bool {
+        matches!(self, GameState::InWorld(_))
+    }
+
+    /// The state's name as written in the state file, without its details.
+    pub fn name(self) -> &'static str {
+        match self {
+            GameState::Unknown => "unknown",
+            GameState::Title => "title",
+            GameState::Waiting => "waiting",
+            GameState::Generating(_) => "generating",
+            GameState::Previewing(_) => "previewing",
+            GameState::InWorld(_) => "inworld",
+            GameState::Wall => "wall",
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("invalid game state: {0}")]
+pub struct ParseGameStateError(String);
+
+impl FromStr for GameState {
+    type Err = ParseGameStateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let error = || ParseGameStateError(s.to_string());
+        let (kind, detail) = match s.split_once(',') {
+            Some((kind, detail)) => (kind, Some(detail)),
+            None => (s, None),
+        };
+        let percent = || {
+            detail
+                .and_then(|detail| detail.parse::<u8>().ok())
+                .ok_or_else(error)
+        };
+
+        Ok(match kind {
+            "title" => GameState::Title,
+            "waiting" => GameState::Waiting,
+            "wall" => GameState::Wall,
+            "generating" => GameState::Generating(percent()?),
+            "previewing" => GameState::Previewing(percent()?),
+            "inworld" => GameState::InWorld(match detail {
+                Some("unpaused") => InWorld::Unpaused,
+                Some("paused") => InWorld::Paused,
+                Some("gamescreenopen") => InWorld::GameScreenOpen,
+                _ => return Err(error()),
+            }),
+            _ => return Err(error()),
+        })
+    }
+}
+
+impl Display for GameState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameState::Unknown => f.write_str("Unknown"),
+            GameState::Title => f.write_str("Title screen"),
+            GameState::Waiting => f.write_str("Waiting"),
+            GameState::Generating(percent) => write!(f, "Generating ({}%)", percent),
+            GameState::Previewing(percent) => write!(f, "Previewing ({}%)", percent),
+            GameState::InWorld(InWorld::Unpaused) => f.write_str("In world"),
+            GameState::InWorld(InWorld::Paused) => f.write_str("Paused"),
+            GameState::InWorld(InWorld::GameScreenOpen) => f.write_str("In menu"),
+            GameState::Wall => f.write_str("Wall"),
+        }
+    }
+}
+
+fn state_path(config: &Config, instances: &InstanceRegistry, hwnd: isize) -> Option<PathBuf> {
+    instances
+        .game_dir(HWND(hwnd as *mut _), config)
+        .map(|game_dir| game_dir.join(STATE_FILE))
+}
+
+fn state_paths(config: &Config, instances: &InstanceRegistry) -> Vec<(isize, Option<PathBuf>)> {
+    instances
+        .all()
+        .into_iter()
+        .map(|instance| registry::key(instance.hwnd))
+        .map(|hwnd| (hwnd, state_path(config, instances, hwnd)))
+        .collect()
+}
+
+/// Reads the state file. A missing or unreadable file is `Unknown`, while a
+/// file caught halfway through being rewritten is `None`, to be read again on
+/// the next change.
+fn read(path: Option<&Path>) -> Option<GameState> {
+    let Some(path) = path else {
+        return Some(GameState::Unknown);
+    };
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Some(GameState::Unknown);
+    };
+
+    match contents.parse() {
+        Ok(state) => Some(state),
+        Err(e) => {
+            log::trace!("{}", e);
+            None
+        }
+    }
+}
+
+/// Watches the state file of every instance, sending each new state along
+/// with the handle of the instance's window. An instance's first state is
+/// sent as soon as it's seen.
+pub fn spawn(
+    config: Arc<ArcSwap<Config>>,
+    instances: InstanceRegistry,
+) -> mpsc::UnboundedReceiver<(isize, GameState)> {
+    let (tx, rx) = mpsc::unbounded();
+
+    let changed = watch::spawn(Duration::from_millis(50), {
+        let config = config.clone();
+        let instances = instances.clone();
+        move || {
+            let paths = state_paths(&config.load(), &instances);
+
+            // Adding an instance changes the fingerprint even when it has no
+            // state file, so that it still gets its first state.
+            watch::fingerprint(
+                paths
+                    .into_iter()
+                    .map(|(hwnd, path)| path.unwrap_or_else(|| PathBuf::from(hwnd.to_string()))),
+            )
+        }
+    });
+
+    std::thread::spawn(move || {
+        let mut last: BTreeMap<isize, GameState> = BTreeMap::new();
+        let mut update = || {
+            let paths = state_paths(&config.load(), &instances);
+            last.retain(|hwnd, _| paths.iter().any(|(open, _)| open == hwnd));
+
+            for (hwnd, path) in paths {
+                if let Some(state) = read(path.as_deref())
+                    && last.insert(hwnd, state) != Some(state)
+                    && tx.unbounded_send((hwnd, state)).is_err()
+                {
+                    return false;
+                }
+            }
+
+            !tx.is_closed()
+        };
+
+        if !update() {
+            return;
+        }
+
+        futures::executor::block_on(async {
+            let mut changed = changed;
+            while changed.next().await.is_some() {
+                if !update() {
+                    break;
+                }
+            }
+        });
+    });
+
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_state() {
+        let cases = [
+            ("title", GameState::Title),
+            ("waiting", GameState::Waiting),
+            ("wall", GameState::Wall),
+            ("generating,0", GameState::Generating(0)),
+            ("generating,42", GameState::Generating(42)),
+            ("previewing,100", GameState::Previewing(100)),
+            ("inworld,unpaused", GameState::InWorld(InWorld::Unpaused)),
+            ("inworld,paused", GameState::InWorld(InWorld::Paused)),
+            (
+                "inworld,gamescreenopen",
+                GameState::InWorld(InWorld::GameScreenOpen),
+            ),
+        ];
+        for (text, state) in cases {
+            assert_eq!(text.parse(), Ok(state), "{}", text);
+            assert_eq!(state.name(), text.split(',').next().unwrap());
+        }
+    }
+
+    #[test]
+    fn ignores_surrounding_whitespace() {
+        assert_eq!("title\n".parse(), Ok(GameState::Title));
+        assert_eq!(
+            "  inworld,paused\r\n".parse(),
+            Ok(GameState::InWorld(InWorld::Paused))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_states() {
+        for text in [
+            "",
+            "menu",
+            "Title",
+            "inworld",
+            "inworld,flying",
+            "inworld, paused",
+            "generating",
+            "generating,",
+            "generating,half",
+            "generating,300",
+        ] {
+            assert_eq!(
+                text.parse::<GameState>(),
+                Err(ParseGameStateError(text.trim().to_string())),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn reads_the_state_file() {
+        let path =
+            std::env::temp_dir().join(format!("wrinkle-{}-{}", std::process::id(), STATE_FILE));
+
+        assert_eq!(read(None), Some(GameState::Unknown));
+        let _ = fs::remove_file(&path);
+        assert_eq!(read(Some(&path)), Some(GameState::Unknown));
+
+        fs::write(&path, "previewing,37").unwrap();
+        assert_eq!(read(Some(&path)), Some(GameState::Previewing(37)));
+
+        // Caught halfway through a rewrite.
+        fs::write(&path, "inwor").unwrap();
+        assert_eq!(read(Some(&path)), None);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
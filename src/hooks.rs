@@ -0,0 +1,160 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    os::windows::process::CommandExt,
+    process::{Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+
+use crate::{
+    config::{
+        Hotkey,
+        hooks::{HookCommand, ModeHooks},
+        per_mode::PerMode,
+        xy::XY,
+    },
+    manager::event::{Event, EventBus},
+};
+
+#[derive(Error, Debug)]
+pub enum HookError {
+    #[error("failed to start: {0}")]
+    Spawn(#[from] std::io::Error),
+
+    #[error("timed out after {0:?}")]
+    TimedOut(Duration),
+
+    #[error("exited with {0}")]
+    Failed(ExitStatus),
+}
+
+/// Whether a hook runs because its mode is being entered or left.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    Enter,
+    Exit,
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Enter => "enter",
+            Phase::Exit => "exit",
+        }
+    }
+}
+
+fn log_lines(program: String, output: impl Read + Send + 'static) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for line in BufReader::new(output).lines().map_while(Result::ok) {
+            log::info!("[{}] {}", program, line);
+        }
+    })
+}
+
+fn execute(command: &HookCommand, env: &[(&str, String)]) -> Result<(), HookError> {
+    let HookCommand {
+        program,
+        args,
+        env: extra_env,
+        timeout,
+    } = command;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .envs(extra_env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .spawn()?;
+
+    let stdout = log_lines(program.clone(), child.stdout.take().unwrap());
+    let stderr = log_lines(program.clone(), child.stderr.take().unwrap());
+
+    let timeout = Duration::from_millis(*timeout);
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(HookError::TimedOut(timeout));
+        }
+
+        thread::sleep(Duration::from_millis(20));
+    };
+
+    let _ = stdout.join();
+    let _ = stderr.join();
+
+    if !status.success() {
+        return Err(HookError::Failed(status));
+    }
+
+    Ok(())
+}
+
+fn mode_name(mode: Option<Hotkey>) -> String {
+    mode.map(Hotkey::name).unwrap_or("normal").to_string()
+}
+
+/// Runs the exit hooks of `from` and then the enter hooks of `to` on a
+/// background thread, one after another, so the input loop is never blocked
+/// on them.
+pub fn spawn(
+    hooks: &PerMode<ModeHooks>,
+    from: Option<Hotkey>,
+    to: Option<Hotkey>,
+    size: XY,
+    events: EventBus,
+) {
+    let exit = from.map(|from| hooks.get(from).on_exit.clone());
+    let enter = to.map(|to| hooks.get(to).on_enter.clone());
+
+    let commands: Vec<(Phase, HookCommand)> = exit
+        .into_iter()
+        .flatten()
+        .map(|command| (Phase::Exit, command))
+        .chain(
+            enter
+                .into_iter()
+                .flatten()
+                .map(|command| (Phase::Enter, command)),
+        )
+        .collect();
+
+    if commands.is_empty() {
+        return;
+    }
+
+    let env = [
+        ("WRINKLE_MODE", mode_name(to)),
+        ("WRINKLE_PREVIOUS_MODE", mode_name(from)),
+        ("WRINKLE_WIDTH", size.x.to_string()),
+        ("WRINKLE_HEIGHT", size.y.to_string()),
+    ];
+
+    thread::spawn(move || {
+        for (phase, command) in commands {
+            log::debug!("Running {} hook: {:?}", phase.name(), command);
+
+            let mut env = env.to_vec();
+            env.push(("WRINKLE_EVENT", phase.name().to_string()));
+
+            if let Err(e) = execute(&command, &env) {
+                let message = format!("Hook {} failed: {}", command.program, e);
+
+                log::error!("{}", message);
+                events.publish(Event::ErrorRaised(message));
+            }
+        }
+    });
+}
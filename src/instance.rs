@@ -1,45 +1,229 @@
-use std::{marker::PhantomData, sync::LazyLock};
+pub mod metadata;
+pub mod registry;
 
+use std::{
+    collections::BTreeSet,
+    marker::PhantomData,
+    sync::{Arc, LazyLock},
+};
+
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
 use widestring::U16Str;
 use windows::{
     Win32::{
-        Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Foundation::{HWND, LPARAM, LRESULT, POINT, RECT, WPARAM},
         Graphics::Gdi::{
             GetMonitorInfoW, MONITOR_DEFAULTTOPRIMARY, MONITORINFO, MonitorFromWindow,
         },
         System::LibraryLoader::GetModuleHandleW,
         UI::WindowsAndMessaging::{
-            DefWindowProcW, EnumWindows, GWL_STYLE, GetForegroundWindow, GetWindowLongW,
-            GetWindowRect, GetWindowTextW, HSHELL_WINDOWCREATED, HWND_MESSAGE, RegisterClassExW,
-            RegisterShellHookWindow, RegisterWindowMessageW, SWP_DEFERERASE, SWP_FRAMECHANGED,
-            SWP_NOCOPYBITS, SWP_NOMOVE, SWP_NOREDRAW, SWP_NOSENDCHANGING, SWP_NOSIZE, SWP_NOZORDER,
-            SetWindowLongW, SetWindowPos, WINDOW_EX_STYLE, WINDOW_STYLE, WNDCLASSEXW, WS_BORDER,
-            WS_DLGFRAME, WS_MAXIMIZEBOX, WS_MINIMIZEBOX, WS_SYSMENU, WS_THICKFRAME,
+            DefWindowProcW, EnumWindows, GWL_EXSTYLE, GWL_STYLE, GetClassNameW, GetClientRect,
+            GetWindowLongW, GetWindowPlacement, GetWindowRect, GetWindowTextW, HSHELL_REDRAW,
+            HSHELL_WINDOWCREATED, HSHELL_WINDOWDESTROYED, HWND_MESSAGE, IsWindow, IsWindowVisible,
+            RegisterClassExW, RegisterShellHookWindow, RegisterWindowMessageW, SWP_DEFERERASE,
+            SWP_FRAMECHANGED, SWP_NOCOPYBITS, SWP_NOMOVE, SWP_NOREDRAW, SWP_NOSENDCHANGING,
+            SWP_NOSIZE, SWP_NOZORDER, SetWindowLongW, SetWindowPlacement, SetWindowPos,
+            WINDOW_EX_STYLE, WINDOW_STYLE, WINDOWPLACEMENT, WNDCLASSEXW, WS_BORDER, WS_DLGFRAME,
+            WS_MAXIMIZEBOX, WS_MINIMIZEBOX, WS_SYSMENU, WS_THICKFRAME,
         },
     },
     core::{BOOL, PCWSTR},
 };
 
 use crate::{
-    config::xy::XY,
+    config::{
+        Config, Normal,
+        detection::{DetectionRules, Detector, Verdict, WindowInfo},
+        xy::XY,
+    },
+    instance::metadata::InstanceMetadata,
+    journal::{self, Entry},
+    process::Process,
     utils::UnsafeSync,
     wnd_class::{self, WndClass, wnd_proc},
 };
 
+/// Window geometry and styles as they were before wrinkle first touched the
+/// window, so that it can be put back exactly as the user left it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub style: i32,
+    pub ex_style: i32,
+    pub position: XY,
+    pub size: XY,
+    pub show_cmd: u32,
+    pub normal_position: XY,
+    pub normal_size: XY,
+}
+
+impl WindowGeometry {
+    pub fn capture(hwnd: HWND) -> windows::core::Result<Self> {
+        unsafe {
+            let style = GetWindowLongW(hwnd, GWL_STYLE);
+            let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE);
+
+            let mut rect = RECT::default();
+            GetWindowRect(hwnd, &raw mut rect)?;
+
+            let mut placement = WINDOWPLACEMENT::default();
+            placement.length = std::mem::size_of_val(&placement) as u32;
+            GetWindowPlacement(hwnd, &raw mut placement)?;
+
+            let normal = placement.rcNormalPosition;
+
+            Ok(Self {
+                style,
+                ex_style,
+                position: XY::new(rect.left, rect.top),
+                size: XY::new(rect.right - rect.left, rect.bottom - rect.top),
+                show_cmd: placement.showCmd,
+                normal_position: XY::new(normal.left, normal.top),
+                normal_size: XY::new(normal.right - normal.left, normal.bottom - normal.top),
+            })
+        }
+    }
+
+    pub fn apply(&self, hwnd: HWND) -> windows::core::Result<()> {
+        let Self {
+            style,
+            ex_style,
+            show_cmd,
+            normal_position,
+            normal_size,
+            ..
+        } = *self;
+
+        unsafe {
+            SetWindowLongW(hwnd, GWL_STYLE, style);
+            SetWindowLongW(hwnd, GWL_EXSTYLE, ex_style);
+
+            let mut placement = WINDOWPLACEMENT::default();
+            placement.length = std::mem::size_of_val(&placement) as u32;
+            placement.showCmd = show_cmd;
+            placement.ptMinPosition = POINT { x: -1, y: -1 };
+            placement.ptMaxPosition = POINT { x: -1, y: -1 };
+            placement.rcNormalPosition = RECT {
+                left: normal_position.x,
+                top: normal_position.y,
+                right: normal_position.x + normal_size.x,
+                bottom: normal_position.y + normal_size.y,
+            };
+            SetWindowPlacement(hwnd, &raw const placement)?;
+
+            SetWindowPos(
+                hwnd,
+                None,
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_FRAMECHANGED,
+            )
+        }
+    }
+}
+
+/// Style bits making up a window's frame, removed for borderless windows.
+const FRAME_STYLE: WINDOW_STYLE = WINDOW_STYLE(
+    WS_BORDER.0
+        | WS_DLGFRAME.0
+        | WS_THICKFRAME.0
+        | WS_MINIMIZEBOX.0
+        | WS_MAXIMIZEBOX.0
+        | WS_SYSMENU.0,
+);
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct MinecraftInstance {
     pub hwnd: HWND,
+    pub original: WindowGeometry,
 }
 unsafe impl Send for MinecraftInstance {}
 unsafe impl Sync for MinecraftInstance {}
 
 impl MinecraftInstance {
-    pub fn new(hwnd: HWND) -> Self {
-        Self { hwnd }
+    pub fn new(hwnd: HWND) -> windows::core::Result<Self> {
+        Ok(Self {
+            hwnd,
+            original: WindowGeometry::capture(hwnd)?,
+        })
     }
 
-    pub fn is_foreground(&self) -> bool {
-        unsafe { self.hwnd == GetForegroundWindow() }
+    /// Resolves the process behind the window and what it's running.
+    pub fn metadata(&self) -> InstanceMetadata {
+        InstanceMetadata::resolve(self.hwnd)
+    }
+
+    fn journal_entry(&self) -> Entry {
+        Entry::Window {
+            hwnd: self.hwnd.0 as isize,
+            geometry: self.original,
+        }
+    }
+
+    /// Puts the window back to the geometry and styles it had when it was
+    /// first found.
+    pub fn restore(&self) {
+        log::debug!("Restoring minecraft instance: {:?}", self.hwnd);
+
+        if let Err(e) = self.original.apply(self.hwnd) {
+            log::warn!(
+                "Failed to restore minecraft instance {:?}: {}",
+                self.hwnd,
+                e
+            );
+        }
+
+        self.forget();
+    }
+
+    /// Drops the instance's journal entry without touching the window, for
+    /// windows that are already gone.
+    pub fn forget(&self) {
+        journal::forget(self.journal_entry());
+    }
+
+    /// Strips the window frame, or puts back the frame the window originally
+    /// had. Style bits other than the frame's are left alone.
+    pub fn set_borderless(&self, borderless: bool) -> windows::core::Result<()> {
+        let frame = FRAME_STYLE.0 as i32;
+
+        unsafe {
+            let current = GetWindowLongW(self.hwnd, GWL_STYLE);
+            let style = if borderless {
+                current & !frame
+            } else {
+                (current & !frame) | (self.original.style & frame)
+            };
+
+            if style == current {
+                return Ok(());
+            }
+
+            log::debug!(
+                "Making minecraft instance {:?} {}",
+                self.hwnd,
+                if borderless { "borderless" } else { "framed" }
+            );
+
+            SetWindowLongW(self.hwnd, GWL_STYLE, style);
+            SetWindowPos(
+                self.hwnd,
+                None,
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_FRAMECHANGED,
+            )
+        }
+    }
+
+    /// Whether the window still exists. Handles of closed windows can be
+    /// reused, so this doesn't guarantee it's still the same window.
+    pub fn is_alive(&self) -> bool {
+        unsafe { IsWindow(Some(self.hwnd)).as_bool() }
     }
 
     pub fn get_monitor_info(&self) -> (XY, XY) {
@@ -63,7 +247,15 @@ impl MinecraftInstance {
         }
     }
 
-    pub fn set_window_pos(&self, rect: (XY, XY)) {
+    pub fn get_normal_rect(&self, normal: Normal) -> (XY, XY) {
+        match normal {
+            Normal::Borderless => self.get_monitor_info(),
+            Normal::Original => (self.original.position, self.original.size),
+            Normal::Custom { position, size } => (position, size),
+        }
+    }
+
+    pub fn set_window_pos(&self, rect: (XY, XY)) -> windows::core::Result<()> {
         let (
             XY { x: left, y: top },
             XY {
@@ -90,63 +282,166 @@ impl MinecraftInstance {
                 height,
                 SWP_NOSENDCHANGING | SWP_NOCOPYBITS | SWP_DEFERERASE,
             )
-            .unwrap()
-        };
+        }
+    }
+
+    pub fn get_window_rect(&self) -> windows::core::Result<RECT> {
+        let mut rect = RECT::default();
+        unsafe {
+            GetWindowRect(self.hwnd, &raw mut rect)?;
+        }
+        Ok(rect)
     }
 
-    pub fn get_window_rect(&self) -> RECT {
+    /// Size of the area the game draws into, without any frame.
+    pub fn get_client_size(&self) -> windows::core::Result<XY> {
         let mut rect = RECT::default();
         unsafe {
-            GetWindowRect(self.hwnd, &raw mut rect).unwrap();
+            GetClientRect(self.hwnd, &raw mut rect)?;
         }
-        rect
+        Ok(XY::new(rect.right - rect.left, rect.bottom - rect.top))
     }
 }
 
+/// What the listener saw happen to Minecraft windows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListenerEvent {
+    Found(MinecraftInstance),
+    /// The window was closed, or no longer looks like an instance.
+    Closed(HWND),
+}
+
 struct MinecraftInstanceListenerWindow {
-    cb: Box<dyn FnMut(HWND)>,
+    config: Arc<ArcSwap<Config>>,
+    /// Windows already reported as found, by handle.
+    found: BTreeSet<isize>,
+    /// The detection rules last loaded, and the detector compiled from them.
+    rules: DetectionRules,
+    detector: Detector,
+    cb: Box<dyn FnMut(ListenerEvent)>,
 }
 
 impl MinecraftInstanceListenerWindow {
+    /// The detector for the current config, compiled again only when the
+    /// rules have changed.
+    fn detector(&mut self) -> &Detector {
+        let config = self.config.load();
+        if config.detection != self.rules {
+            self.rules = config.detection.clone();
+            self.detector = Detector::new(&self.rules);
+        }
+
+        &self.detector
+    }
+
     fn run_cb(&mut self, hwnd: HWND) {
         log::debug!("Found new minecraft instance: {:?}", hwnd);
-        unsafe {
-            let mut style = GetWindowLongW(hwnd, GWL_STYLE);
-            style &= !(WS_BORDER
-                | WS_DLGFRAME
-                | WS_THICKFRAME
-                | WS_MINIMIZEBOX
-                | WS_MAXIMIZEBOX
-                | WS_SYSMENU)
-                .0 as i32;
-            SetWindowLongW(hwnd, GWL_STYLE, style);
 
-            SetWindowPos(
-                hwnd,
-                None,
-                0,
-                0,
-                0,
-                0,
-                SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_FRAMECHANGED,
-            )
-            .unwrap();
-        }
+        // The window may already be gone by the time it's looked at.
+        let instance = match MinecraftInstance::new(hwnd) {
+            Ok(instance) => instance,
+            Err(e) => {
+                log::warn!("Failed to read minecraft instance {:?}: {}", hwnd, e);
+                return;
+            }
+        };
+        journal::record(instance.journal_entry());
 
-        (self.cb)(hwnd)
+        self.found.insert(hwnd.0 as isize);
+        (self.cb)(ListenerEvent::Found(instance))
     }
 }
 
-fn is_minecraft_window(hwnd: HWND) -> bool {
-    unsafe {
-        let mut str = [0u16; 256];
-        let len = GetWindowTextW(hwnd, &mut str[..]) as usize;
-        let str = U16Str::from_slice(&str[..len]);
+fn window_text(hwnd: HWND) -> String {
+    let mut buf = [0u16; 256];
+    let len = unsafe { GetWindowTextW(hwnd, &mut buf[..]) } as usize;
 
-        const MINECRAFT: &U16Str = widestring::u16str!("Minecraft*");
+    U16Str::from_slice(&buf[..len]).to_string_lossy()
+}
+
+fn class_name(hwnd: HWND) -> String {
+    let mut buf = [0u16; 256];
+    let len = unsafe { GetClassNameW(hwnd, &mut buf[..]) } as usize;
+
+    U16Str::from_slice(&buf[..len]).to_string_lossy()
+}
+
+/// The title and class of `hwnd`, without looking up its process.
+fn window_basics(hwnd: HWND) -> WindowInfo {
+    WindowInfo {
+        title: window_text(hwnd),
+        class: class_name(hwnd),
+        ..WindowInfo::default()
+    }
+}
+
+/// Fills in what `window` knows about the process owning `hwnd`.
+fn resolve_process(hwnd: HWND, window: &mut WindowInfo) {
+    let process = Process::of_window(hwnd);
+
+    window.executable = process
+        .as_ref()
+        .and_then(Process::executable)
+        .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()));
+    window.command_line = process.as_ref().and_then(Process::command_line);
+}
+
+pub fn window_info(hwnd: HWND) -> WindowInfo {
+    let mut window = window_basics(hwnd);
+    resolve_process(hwnd, &mut window);
+
+    window
+}
+
+/// Whether `detector` takes `hwnd` for a Minecraft window. The process is
+/// only opened if the title and class leave a rule needing it.
+pub fn is_minecraft_window(hwnd: HWND, detector: &Detector) -> bool {
+    detector
+        .judge(&mut window_basics(hwnd), |window| {
+            resolve_process(hwnd, window)
+        })
+        .is_match()
+}
 
-        str.as_slice().starts_with(MINECRAFT.as_slice())
+/// A top level window and how the detection rules judged it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Detection {
+    pub hwnd: isize,
+    pub window: WindowInfo,
+    pub verdict: Verdict,
+}
+
+/// Runs the detection rules over every visible, titled top level window,
+/// to show why each one was or wasn't taken for an instance.
+pub fn detect_windows(rules: &DetectionRules) -> Vec<Detection> {
+    let detector = Detector::new(rules);
+
+    unsafe extern "system" fn cb(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        unsafe {
+            let hwnds = &mut *(lparam.0 as *mut Vec<HWND>);
+            if IsWindowVisible(hwnd).as_bool() {
+                hwnds.push(hwnd);
+            }
+
+            BOOL::from(true)
+        }
+    }
+
+    let mut hwnds: Vec<HWND> = Vec::new();
+    unsafe {
+        let _ = EnumWindows(Some(cb), LPARAM(&raw mut hwnds as isize));
     }
+
+    hwnds
+        .into_iter()
+        .map(|hwnd| (hwnd, window_info(hwnd)))
+        .filter(|(_, window)| !window.title.is_empty())
+        .map(|(hwnd, mut window)| Detection {
+            hwnd: hwnd.0 as isize,
+            verdict: detector.judge(&mut window, |_| {}),
+            window,
+        })
+        .collect()
 }
 
 impl WndClass for MinecraftInstanceListenerWindow {
@@ -180,12 +475,39 @@ impl WndClass for MinecraftInstanceListenerWindow {
                 (shell_hook_msg, WPARAM(HSHELL_WINDOWCREATED as usize))
             );
 
-            if msg == shell_hook_msg && wparam == WPARAM(HSHELL_WINDOWCREATED as usize) {
-                log::trace!("New window created: {:?}", hwnd);
-                let hwnd: HWND = HWND(lparam.0 as *mut _);
-
-                if is_minecraft_window(hwnd) {
-                    self.run_cb(hwnd);
+            if msg == shell_hook_msg {
+                let window: HWND = HWND(lparam.0 as *mut _);
+
+                // Flashing windows send `HSHELL_REDRAW` with the high bit set.
+                match wparam.0 as u32 & 0x7FFF {
+                    // Minecraft sets its title after creating the window, so
+                    // title changes are checked as well.
+                    HSHELL_WINDOWCREATED | HSHELL_REDRAW
+                        if !self.found.contains(&(window.0 as isize)) =>
+                    {
+                        log::trace!("Window created or retitled: {:?}", window);
+
+                        if is_minecraft_window(window, self.detector()) {
+                            self.run_cb(window);
+                        }
+                    }
+                    // A found window retitled to something else is no longer
+                    // an instance.
+                    HSHELL_REDRAW
+                        if self.found.contains(&(window.0 as isize))
+                            && !is_minecraft_window(window, self.detector()) =>
+                    {
+                        log::info!("Minecraft instance retitled: {:?}", window);
+
+                        self.found.remove(&(window.0 as isize));
+                        (self.cb)(ListenerEvent::Closed(window));
+                    }
+                    HSHELL_WINDOWDESTROYED if self.found.remove(&(window.0 as isize)) => {
+                        log::info!("Minecraft instance closed: {:?}", window);
+
+                        (self.cb)(ListenerEvent::Closed(window));
+                    }
+                    _ => {}
                 }
             }
 
@@ -199,8 +521,15 @@ pub struct MinecraftInstanceListener {
 }
 
 impl MinecraftInstanceListener {
-    pub fn spawn(cb: Box<dyn FnMut(HWND)>) -> Self {
-        let mut state = Box::new(MinecraftInstanceListenerWindow { cb });
+    pub fn spawn(config: Arc<ArcSwap<Config>>, cb: Box<dyn FnMut(ListenerEvent)>) -> Self {
+        let rules = config.load().detection.clone();
+        let mut state = Box::new(MinecraftInstanceListenerWindow {
+            config,
+            found: BTreeSet::new(),
+            detector: Detector::new(&rules),
+            rules,
+            cb,
+        });
 
         unsafe {
             EnumWindows(Some(Self::cb), LPARAM(state.as_mut() as *mut _ as isize)).unwrap();
@@ -229,7 +558,7 @@ impl MinecraftInstanceListener {
         unsafe {
             let state = &mut *(lparam.0 as *mut MinecraftInstanceListenerWindow);
 
-            if is_minecraft_window(hwnd) {
+            if is_minecraft_window(hwnd, state.detector()) {
                 state.run_cb(hwnd);
             }
 
@@ -0,0 +1,369 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use regex::Regex;
+use windows::Win32::Foundation::HWND;
+
+use crate::process::Process;
+
+/// Splits a command line into arguments the way the Microsoft C runtime
+/// does: whitespace separates arguments unless quoted, `\"` is a literal
+/// quote, and backslashes are only special right before a quote.
+pub fn split_command_line(command_line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut chars = command_line.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return args;
+        }
+
+        let mut arg = String::new();
+        let mut quoted = false;
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    let mut backslashes = 1;
+                    while chars.next_if_eq(&'\\').is_some() {
+                        backslashes += 1;
+                    }
+
+                    if chars.peek() == Some(&'"') {
+                        arg.extend(std::iter::repeat_n('\\', backslashes / 2));
+                        if backslashes % 2 == 1 {
+                            arg.push('"');
+                            chars.next();
+                        }
+                    } else {
+                        arg.extend(std::iter::repeat_n('\\', backslashes));
+                    }
+                }
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    arg.push('"');
+                    chars.next();
+                }
+                '"' => quoted = !quoted,
+                c if c.is_whitespace() && !quoted => break,
+                c => arg.push(c),
+            }
+        }
+
+        args.push(arg);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LauncherKind {
+    Vanilla,
+    MultiMC,
+    Prism,
+}
+
+impl Display for LauncherKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LauncherKind::Vanilla => f.write_str("Vanilla"),
+            LauncherKind::MultiMC => f.write_str("MultiMC"),
+            LauncherKind::Prism => f.write_str("Prism"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Launcher {
+    pub kind: LauncherKind,
+    /// Name of the launcher's instance, for launchers that have them.
+    pub instance_name: Option<String>,
+}
+
+/// What's known about the game running in an instance's window.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InstanceMetadata {
+    pub pid: Option<u32>,
+    pub executable: Option<PathBuf>,
+    pub game_dir: Option<PathBuf>,
+    pub version: Option<String>,
+    pub launcher: Option<Launcher>,
+}
+
+/// Library paths in the classpath that carry the game version, for vanilla
+/// (`versions/1.16.1/1.16.1.jar`) and MultiMC-style launchers
+/// (`com/mojang/minecraft/1.16.1/minecraft-1.16.1-client.jar`).
+static VERSION_IN_CLASSPATH: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:versions[/\\]([^/\\;]+)[/\\][^/\\;]+\.jar|com[/\\]mojang[/\\]minecraft[/\\]([^/\\;]+)[/\\])")
+        .unwrap()
+});
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+/// Splits off the last component of a path with either kind of separator,
+/// so Windows paths are handled the same on any platform.
+fn split_last(path: &str) -> Option<(&str, &str)> {
+    path.trim_end_matches(['/', '\\']).rsplit_once(['/', '\\'])
+}
+
+fn property_value<'a>(args: &'a [String], property: &str) -> Option<&'a str> {
+    args.iter().find_map(|arg| {
+        arg.strip_prefix("-D")?
+            .strip_prefix(property)?
+            .strip_prefix('=')
+    })
+}
+
+impl InstanceMetadata {
+    /// Reads what it can from a Java command line. Paths are taken as they
+    /// appear and never checked against the file system.
+    pub fn parse(args: &[String]) -> Self {
+        let launcher_kind = if args.iter().any(|arg| arg == "org.prismlauncher.EntryPoint") {
+            Some(LauncherKind::Prism)
+        } else if args.iter().any(|arg| arg == "org.multimc.EntryPoint") {
+            Some(LauncherKind::MultiMC)
+        } else if flag_value(args, "--versionType").is_some()
+            || property_value(args, "minecraft.launcher.brand").is_some()
+        {
+            Some(LauncherKind::Vanilla)
+        } else {
+            None
+        };
+
+        // MultiMC and its forks pass game arguments through stdin, but keep
+        // each instance's natives in `<instance>/natives`.
+        let instance_dir = match launcher_kind {
+            Some(LauncherKind::MultiMC | LauncherKind::Prism) => {
+                property_value(args, "java.library.path")
+                    .and_then(split_last)
+                    .filter(|&(_, last)| last == "natives")
+                    .map(|(dir, _)| dir)
+            }
+            _ => None,
+        };
+
+        let game_dir = flag_value(args, "--gameDir")
+            .map(PathBuf::from)
+            .or_else(|| instance_dir.map(|dir| Path::new(dir).join(".minecraft")));
+
+        let version = flag_value(args, "--version")
+            .map(str::to_string)
+            .or_else(|| {
+                let classpath = flag_value(args, "-cp").or(flag_value(args, "-classpath"))?;
+                let captures = VERSION_IN_CLASSPATH.captures(classpath)?;
+                let version = captures.get(1).or(captures.get(2))?;
+                Some(version.as_str().to_string())
+            });
+
+        let launcher = launcher_kind.map(|kind| Launcher {
+            kind,
+            instance_name: instance_dir
+                .and_then(split_last)
+                .map(|(_, name)| name.to_string()),
+        });
+
+        Self {
+            pid: None,
+            executable: None,
+            game_dir,
+            version,
+            launcher,
+        }
+    }
+
+    /// Looks up the process owning `hwnd` and parses its command line.
+    pub fn resolve(hwnd: HWND) -> Self {
+        let Some(process) = Process::of_window(hwnd) else {
+            return Self::default();
+        };
+
+        let args = process
+            .command_line()
+            .map(|command_line| split_command_line(&command_line))
+            .unwrap_or_default();
+
+        let mut metadata = Self::parse(&args);
+        metadata.pid = Some(process.pid());
+        metadata.executable = process.executable();
+
+        // Newer MultiMC forks name the folder `minecraft`, without the dot.
+        if let Some(game_dir) = &metadata.game_dir
+            && !game_dir.exists()
+            && let Some(alternative) = game_dir.parent().map(|dir| dir.join("minecraft"))
+            && alternative.exists()
+        {
+            metadata.game_dir = Some(alternative);
+        }
+
+        metadata
+    }
+}
+
+impl Display for InstanceMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.version.as_deref().unwrap_or("unknown version"))?;
+
+        if let Some(launcher) = &self.launcher {
+            write!(f, ", {}", launcher.kind)?;
+            if let Some(name) = &launcher.instance_name {
+                write!(f, " \"{}\"", name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(command_line: &str) -> Vec<String> {
+        split_command_line(command_line)
+    }
+
+    fn parse(command_line: &str) -> InstanceMetadata {
+        InstanceMetadata::parse(&split(command_line))
+    }
+
+    #[test]
+    fn splits_on_whitespace_outside_quotes() {
+        assert_eq!(split("  a b\t c  "), ["a", "b", "c"]);
+        assert_eq!(
+            split(r#""C:\Program Files\Java\bin\javaw.exe" -Xmx2G"#),
+            [r"C:\Program Files\Java\bin\javaw.exe", "-Xmx2G"]
+        );
+        assert_eq!(split(r#"-Dname="a b"c"#), ["-Dname=a bc"]);
+        assert_eq!(split(r#""" x"#), ["", "x"]);
+        assert!(split("   ").is_empty());
+    }
+
+    #[test]
+    fn backslashes_are_only_special_before_quotes() {
+        assert_eq!(split(r"C:\a\\b\"), [r"C:\a\\b\"]);
+        assert_eq!(split(r#"a\"b"#), [r#"a"b"#]);
+        assert_eq!(split(r#"a\\\"b"#), [r#"a\"b"#]);
+        assert_eq!(split(r#""a\\" b"#), [r"a\", "b"]);
+        assert_eq!(split(r#""a""b""#), [r#"a"b"#]);
+    }
+
+    #[test]
+    fn reads_the_vanilla_launcher() {
+        let metadata = parse(
+            r#"C:\jdk\bin\javaw.exe -Dminecraft.launcher.brand=minecraft-launcher -cp C:\Users\a\AppData\Roaming\.minecraft\libraries\x.jar;C:\Users\a\AppData\Roaming\.minecraft\versions\1.16.1\1.16.1.jar net.minecraft.client.main.Main --username a --version 1.16.1 --gameDir "C:\Users\a\AppData\Roaming\.minecraft" --versionType release"#,
+        );
+
+        assert_eq!(
+            metadata,
+            InstanceMetadata {
+                pid: None,
+                executable: None,
+                game_dir: Some(PathBuf::from(r"C:\Users\a\AppData\Roaming\.minecraft")),
+                version: Some("1.16.1".to_string()),
+                launcher: Some(Launcher {
+                    kind: LauncherKind::Vanilla,
+                    instance_name: None,
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn finds_the_version_in_the_classpath() {
+        let vanilla = parse(
+            r"javaw -classpath C:\mc\libraries\x.jar;C:\mc\versions\1.16.1\1.16.1.jar net.minecraft.client.main.Main",
+        );
+        assert_eq!(vanilla.version.as_deref(), Some("1.16.1"));
+
+        let multimc = parse(
+            "java -cp /mmc/libraries/com/mojang/minecraft/1.15.2/minecraft-1.15.2-client.jar:/mmc/libraries/x.jar org.multimc.EntryPoint",
+        );
+        assert_eq!(multimc.version.as_deref(), Some("1.15.2"));
+
+        assert_eq!(parse("java -cp x.jar Main").version, None);
+    }
+
+    #[test]
+    fn names_multimc_instances_from_their_natives() {
+        let metadata = parse(
+            r#"javaw "-Djava.library.path=C:\MultiMC\instances\Ranked 1.16\natives" -cp C:\MultiMC\libraries\com\mojang\minecraft\1.16.1\minecraft-1.16.1-client.jar org.multimc.EntryPoint"#,
+        );
+
+        assert_eq!(
+            metadata.game_dir,
+            Some(Path::new(r"C:\MultiMC\instances\Ranked 1.16").join(".minecraft"))
+        );
+        assert_eq!(metadata.version.as_deref(), Some("1.16.1"));
+        assert_eq!(
+            metadata.launcher,
+            Some(Launcher {
+                kind: LauncherKind::MultiMC,
+                instance_name: Some("Ranked 1.16".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn names_prism_instances_from_their_natives() {
+        let metadata = parse(
+            "java -Djava.library.path=/home/a/.local/share/PrismLauncher/instances/MCSR/natives/ org.prismlauncher.EntryPoint",
+        );
+
+        assert_eq!(
+            metadata.game_dir,
+            Some(Path::new("/home/a/.local/share/PrismLauncher/instances/MCSR").join(".minecraft"))
+        );
+        assert_eq!(
+            metadata.launcher,
+            Some(Launcher {
+                kind: LauncherKind::Prism,
+                instance_name: Some("MCSR".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn only_natives_folders_name_instances() {
+        let metadata =
+            parse(r"javaw -Djava.library.path=C:\Prism\libs org.prismlauncher.EntryPoint");
+
+        assert_eq!(metadata.game_dir, None);
+        assert_eq!(
+            metadata.launcher,
+            Some(Launcher {
+                kind: LauncherKind::Prism,
+                instance_name: None,
+            })
+        );
+    }
+
+    #[test]
+    fn game_dir_flag_wins_over_natives() {
+        let metadata = parse(
+            r"javaw -Djava.library.path=C:\MultiMC\instances\A\natives org.multimc.EntryPoint --gameDir D:\worlds\A",
+        );
+
+        assert_eq!(metadata.game_dir, Some(PathBuf::from(r"D:\worlds\A")));
+        assert_eq!(
+            metadata
+                .launcher
+                .and_then(|launcher| launcher.instance_name),
+            Some("A".to_string())
+        );
+    }
+
+    #[test]
+    fn other_programs_have_no_launcher() {
+        assert_eq!(
+            parse("java -jar server.jar nogui"),
+            InstanceMetadata::default()
+        );
+        assert_eq!(InstanceMetadata::parse(&[]), InstanceMetadata::default());
+    }
+}
@@ -0,0 +1,110 @@
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+use arc_swap::{ArcSwap, ArcSwapOption};
+use windows::Win32::{Foundation::HWND, UI::WindowsAndMessaging::GetForegroundWindow};
+
+use crate::{
+    config::Config,
+    instance::{MinecraftInstance, metadata::InstanceMetadata},
+};
+
+/// Handles aren't `Ord`, so instances are keyed by the handle's value.
+pub fn key(hwnd: HWND) -> isize {
+    hwnd.0 as isize
+}
+
+/// Every Minecraft window wrinkle is attached to, keyed by window handle.
+///
+/// One of them is active: the one a mode was last applied to, or failing
+/// that the first one found. The projector and scripts follow it.
+#[derive(Clone, Debug, Default)]
+pub struct InstanceRegistry {
+    instances: Arc<ArcSwap<BTreeMap<isize, MinecraftInstance>>>,
+    metadata: Arc<ArcSwap<BTreeMap<isize, Arc<InstanceMetadata>>>>,
+    active: Arc<ArcSwapOption<MinecraftInstance>>,
+}
+
+impl InstanceRegistry {
+    /// Adds `instance`, returning `false` if its window was already known.
+    pub fn insert(&self, instance: MinecraftInstance) -> bool {
+        let mut inserted = false;
+        self.instances.rcu(|instances| {
+            let mut instances = BTreeMap::clone(instances);
+            inserted = !instances.contains_key(&key(instance.hwnd));
+            instances.entry(key(instance.hwnd)).or_insert(instance);
+            instances
+        });
+
+        if inserted {
+            let metadata = Arc::new(instance.metadata());
+            log::info!("Instance {:?} is {:?}", instance.hwnd, metadata);
+
+            self.metadata.rcu(|all| {
+                let mut all = BTreeMap::clone(all);
+                all.insert(key(instance.hwnd), metadata.clone());
+                all
+            });
+        }
+
+        if inserted && self.active.load().is_none() {
+            self.active.store(Some(Arc::new(instance)));
+        }
+
+        inserted
+    }
+
+    pub fn remove(&self, hwnd: HWND) -> Option<MinecraftInstance> {
+        let mut removed = None;
+        self.instances.rcu(|instances| {
+            let mut instances = BTreeMap::clone(instances);
+            removed = instances.remove(&key(hwnd));
+            instances
+        });
+        self.metadata.rcu(|all| {
+            let mut all = BTreeMap::clone(all);
+            all.remove(&key(hwnd));
+            all
+        });
+
+        if self.active().is_some_and(|active| active.hwnd == hwnd) {
+            let next = self.instances.load().values().next().copied();
+            self.active.store(next.map(Arc::new));
+        }
+
+        removed
+    }
+
+    pub fn get(&self, hwnd: HWND) -> Option<MinecraftInstance> {
+        self.instances.load().get(&key(hwnd)).copied()
+    }
+
+    /// Metadata resolved when the instance was added.
+    pub fn metadata(&self, hwnd: HWND) -> Option<Arc<InstanceMetadata>> {
+        self.metadata.load().get(&key(hwnd)).cloned()
+    }
+
+    /// Where the instance's game files are: its own game directory, or
+    /// failing that the configured one.
+    pub fn game_dir(&self, hwnd: HWND, config: &Config) -> Option<PathBuf> {
+        self.metadata(hwnd)
+            .and_then(|metadata| metadata.game_dir.clone())
+            .or_else(|| config.game_dir.clone())
+    }
+
+    pub fn all(&self) -> Vec<MinecraftInstance> {
+        self.instances.load().values().copied().collect()
+    }
+
+    /// The instance whose window has keyboard focus, if any.
+    pub fn foreground(&self) -> Option<MinecraftInstance> {
+        self.get(unsafe { GetForegroundWindow() })
+    }
+
+    pub fn active(&self) -> Option<MinecraftInstance> {
+        self.active.load().as_deref().copied()
+    }
+
+    pub fn set_active(&self, instance: MinecraftInstance) {
+        self.active.store(Some(Arc::new(instance)));
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    fs::{File, create_dir_all, read_to_string, remove_file, rename},
+    io::Write,
+    path::PathBuf,
+    sync::{LazyLock, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+use windows::Win32::{Foundation::HWND, UI::WindowsAndMessaging::IsWindow};
+
+use crate::{
+    config::{
+        PROJECT_DIR,
+        detection::{DetectionRules, Detector},
+    },
+    instance::{WindowGeometry, is_minecraft_window},
+    mouse::MouseState,
+};
+
+static JOURNAL: LazyLock<Mutex<Journal>> = LazyLock::new(|| Mutex::new(Journal::load()));
+
+/// A system or window setting as it was before wrinkle changed it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Entry {
+    Mouse(MouseState),
+    Window {
+        hwnd: isize,
+        geometry: WindowGeometry,
+    },
+}
+
+impl Entry {
+    fn same_target(&self, other: &Entry) -> bool {
+        match (self, other) {
+            (Entry::Mouse(_), Entry::Mouse(_)) => true,
+            (Entry::Window { hwnd: a, .. }, Entry::Window { hwnd: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+
+    fn restore(&self, detector: &Detector) {
+        log::info!("Restoring from journal: {:?}", self);
+
+        match *self {
+            Entry::Mouse(state) => state.write(),
+            Entry::Window { hwnd, geometry } => {
+                let hwnd = HWND(hwnd as *mut _);
+
+                unsafe {
+                    if !IsWindow(Some(hwnd)).as_bool() || !is_minecraft_window(hwnd, detector) {
+                        log::warn!("Journaled window {:?} is gone, skipping", hwnd);
+                        return;
+                    }
+                }
+
+                if let Err(e) = geometry.apply(hwnd) {
+                    log::warn!("Failed to restore window {:?}: {}", hwnd, e);
+                }
+            }
+        }
+    }
+}
+
+/// Settings wrinkle has changed and not yet put back, persisted before each
+/// change so they can be restored even if the process dies without running
+/// its destructors.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Journal {
+    entries: Vec<Entry>,
+}
+
+impl Journal {
+    fn path() -> PathBuf {
+        PROJECT_DIR.data_local_dir().join("journal.toml")
+    }
+
+    fn load() -> Self {
+        let Ok(contents) = read_to_string(Self::path()) else {
+            return Self::default();
+        };
+
+        toml::from_str(&contents).unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable journal: {}", e);
+            Self::default()
+        })
+    }
+
+    fn save(&self) {
+        if let Err(e) = self.try_save() {
+            log::error!("Failed to write journal: {}", e);
+        }
+    }
+
+    fn try_save(&self) -> std::io::Result<()> {
+        let path = Self::path();
+
+        if self.entries.is_empty() {
+            return match remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+
+        create_dir_all(path.parent().unwrap())?;
+
+        let tmp = path.with_extension("toml.tmp");
+        let mut file = File::create(&tmp)?;
+        write!(file, "{}", toml::to_string(self).unwrap())?;
+        file.sync_all()?;
+        drop(file);
+
+        rename(tmp, path)
+    }
+}
+
+/// Records the original value of a setting that is about to be changed. An
+/// existing entry for the same setting is kept, as it holds the value from
+/// before wrinkle touched it at all.
+pub fn record(entry: Entry) {
+    let mut journal = JOURNAL.lock().unwrap();
+
+    if journal.entries.iter().any(|e| e.same_target(&entry)) {
+        return;
+    }
+
+    journal.entries.push(entry);
+    journal.save();
+}
+
+/// Drops the entry for a setting that has been put back.
+pub fn forget(entry: Entry) {
+    let mut journal = JOURNAL.lock().unwrap();
+
+    let len = journal.entries.len();
+    journal.entries.retain(|e| !e.same_target(&entry));
+
+    if journal.entries.len() != len {
+        journal.save();
+    }
+}
+
+/// Restores everything left over from a previous run. Windows are only
+/// touched if `rules` still take them for a Minecraft instance, in case the
+/// handle was reused.
+pub fn replay(rules: &DetectionRules) {
+    let mut journal = JOURNAL.lock().unwrap();
+    let detector = Detector::new(rules);
+
+    for entry in journal.entries.drain(..) {
+        entry.restore(&detector);
+    }
+
+    journal.save();
+}
@@ -191,6 +191,9 @@ impl WndClass for KeyLoggerWnd {
                         std::mem::size_of::<RAWINPUTHEADER>() as u32,
                     ) > 0
                         && input.header.dwType == RIM_TYPEKEYBOARD.0
+                        // Input injected with SendInput, such as macros'
+                        // own key presses, comes from no device.
+                        && !input.header.hDevice.0.is_null()
                     {
                         match input.data.keyboard {
                             RAWKEYBOARD {
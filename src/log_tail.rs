@@ -0,0 +1,515 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use arc_swap::ArcSwap;
+use futures_channel::mpsc;
+use regex::{Captures, Regex};
+
+use crate::{
+    config::{Config, log_pattern::LogPattern},
+    instance::registry::{self, InstanceRegistry},
+};
+
+/// Something the game wrote to `latest.log`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogEvent {
+    WorldJoined {
+        player: String,
+    },
+    WorldLeft,
+    /// A dimension was loaded, such as `minecraft:the_nether`.
+    Dimension(String),
+    Advancement {
+        player: String,
+        advancement: String,
+    },
+    Death {
+        player: String,
+        message: String,
+    },
+    Chat(String),
+    /// A line matching one of the configured [`LogPattern`]s, with its
+    /// capture groups.
+    Custom {
+        name: String,
+        groups: Vec<String>,
+    },
+}
+
+impl LogEvent {
+    pub fn name(&self) -> &str {
+        match self {
+            LogEvent::WorldJoined { .. } => "world_joined",
+            LogEvent::WorldLeft => "world_left",
+            LogEvent::Dimension(_) => "dimension",
+            LogEvent::Advancement { .. } => "advancement",
+            LogEvent::Death { .. } => "death",
+            LogEvent::Chat(_) => "chat",
+            LogEvent::Custom { name, .. } => name,
+        }
+    }
+}
+
+/// `[12:34:56] [Server thread/INFO]: message`, with newer versions adding
+/// the logger name in parentheses, and Fabric dropping the colon.
+static LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[[^\]]*\] \[[^\]]*/\w+\](?: \([^)]*\))?:? (.*)$").unwrap());
+
+/// The start of every vanilla death message. Player chat starts with the
+/// `<name>` of its sender instead.
+static DEATH: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^([^<\s]\S*) (was |fell |drowned|burned|blew up|hit the ground|went up in flames|went off with a bang|walked into|tried to swim|died|starved|suffocated|froze|withered|experienced kinetic|discovered the floor|didn't want to live|left the confines|was squashed|was killed)",
+    )
+    .unwrap()
+});
+
+type Parse = fn(&Captures) -> LogEvent;
+
+/// Built-in message formats, tried in order on the message part of a line.
+static BUILTIN: LazyLock<Vec<(Regex, Parse)>> = LazyLock::new(|| {
+    let table: [(&str, Parse); 5] = [
+        (r"^(\S+) joined the game$", |c| LogEvent::WorldJoined {
+            player: c[1].to_string(),
+        }),
+        (r"^Stopping (?:singleplayer )?server", |_| {
+            LogEvent::WorldLeft
+        }),
+        (r"^Preparing start region for dimension (\S+)", |c| {
+            LogEvent::Dimension(c[1].to_string())
+        }),
+        (
+            r"^\[CHAT\] (\S+) has (?:made the advancement|completed the challenge|reached the goal) \[(.+)\]$",
+            |c| LogEvent::Advancement {
+                player: c[1].to_string(),
+                advancement: c[2].to_string(),
+            },
+        ),
+        (r"^\[CHAT\] (.*)$", |c| match DEATH.captures(&c[1]) {
+            Some(death) => LogEvent::Death {
+                player: death[1].to_string(),
+                message: c[1].to_string(),
+            },
+            None => LogEvent::Chat(c[1].to_string()),
+        }),
+    ];
+
+    table
+        .into_iter()
+        .map(|(regex, parse)| (Regex::new(regex).unwrap(), parse))
+        .collect()
+});
+
+fn compile(patterns: &[LogPattern]) -> Vec<(String, Regex)> {
+    patterns
+        .iter()
+        .filter_map(|pattern| match Regex::new(&pattern.regex) {
+            Ok(regex) => Some((pattern.name.clone(), regex)),
+            Err(e) => {
+                log::warn!("Invalid log pattern {}: {}", pattern.name, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Parses one log line. Built-in formats are tried on the message part of
+/// the line, and `custom` patterns after them on the whole line.
+pub fn parse_line(line: &str, custom: &[(String, Regex)]) -> Option<LogEvent> {
+    let builtin = LINE.captures(line).and_then(|captures| {
+        let message = captures.get(1)?.as_str();
+
+        BUILTIN
+            .iter()
+            .find_map(|(regex, parse)| regex.captures(message).map(|captures| parse(&captures)))
+    });
+
+    builtin.or_else(|| {
+        custom.iter().find_map(|(name, regex)| {
+            let captures = regex.captures(line)?;
+
+            Some(LogEvent::Custom {
+                name: name.clone(),
+                groups: captures
+                    .iter()
+                    .skip(1)
+                    .map(|group| group.map(|m| m.as_str().to_string()).unwrap_or_default())
+                    .collect(),
+            })
+        })
+    })
+}
+
+/// Follows a growing log file. The game starts a new `latest.log` on every
+/// launch, which shows as the file shrinking or being created anew, and
+/// reading then starts over from the top.
+struct Tail {
+    path: PathBuf,
+    position: u64,
+    created: Option<SystemTime>,
+    partial: Vec<u8>,
+}
+
+impl Tail {
+    /// Starts following `path` from its current end, skipping what's
+    /// already there.
+    fn new(path: PathBuf) -> Self {
+        let (position, created) = match File::open(&path).and_then(|file| file.metadata()) {
+            Ok(metadata) => (metadata.len(), metadata.created().ok()),
+            Err(_) => (0, None),
+        };
+
+        Self {
+            path,
+            position,
+            created,
+            partial: Vec::new(),
+        }
+    }
+
+    fn read_lines(&mut self) -> Vec<String> {
+        // The handle's metadata, unlike the directory entry's, is up to date
+        // while the game holds the file open.
+        let Ok(mut file) = File::open(&self.path) else {
+            return Vec::new();
+        };
+        let Ok(metadata) = file.metadata() else {
+            return Vec::new();
+        };
+
+        let created = metadata.created().ok();
+        if metadata.len() < self.position || created != self.created {
+            log::debug!("{} was rotated", self.path.display());
+
+            self.position = 0;
+            self.created = created;
+            self.partial.clear();
+        }
+
+        if metadata.len() == self.position || file.seek(SeekFrom::Start(self.position)).is_err() {
+            return Vec::new();
+        }
+
+        let mut buf = std::mem::take(&mut self.partial);
+        let Ok(read) = file.read_to_end(&mut buf) else {
+            return Vec::new();
+        };
+        self.position += read as u64;
+
+        let complete = buf.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        self.partial = buf.split_off(complete);
+
+        String::from_utf8_lossy(&buf)
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect()
+    }
+}
+
+fn log_path(game_dir: &Path) -> PathBuf {
+    game_dir.join("logs").join("latest.log")
+}
+
+/// Tails the `latest.log` of every instance with a known game directory,
+/// sending the events parsed from new lines along with the handle of the
+/// instance's window.
+pub fn spawn(
+    config: Arc<ArcSwap<Config>>,
+    instances: InstanceRegistry,
+) -> mpsc::UnboundedReceiver<(isize, LogEvent)> {
+    let (tx, rx) = mpsc::unbounded();
+
+    thread::spawn(move || {
+        let mut tails: BTreeMap<isize, Tail> = BTreeMap::new();
+        let mut patterns: Vec<LogPattern> = Vec::new();
+        let mut custom = Vec::new();
+
+        while !tx.is_closed() {
+            let config = config.load();
+            if config.log_patterns != patterns {
+                patterns = config.log_patterns.clone();
+                custom = compile(&patterns);
+            }
+
+            let paths: BTreeMap<isize, PathBuf> = instances
+                .all()
+                .into_iter()
+                .filter_map(|instance| {
+                    let game_dir = instances.game_dir(instance.hwnd, &config)?;
+
+                    Some((registry::key(instance.hwnd), log_path(&game_dir)))
+                })
+                .collect();
+
+            tails.retain(|hwnd, tail| paths.get(hwnd) == Some(&tail.path));
+            for (hwnd, path) in paths {
+                tails.entry(hwnd).or_insert_with(|| Tail::new(path));
+            }
+
+            for (&hwnd, tail) in &mut tails {
+                for line in tail.read_lines() {
+                    if let Some(event) = parse_line(&line, &custom) {
+                        log::debug!("Log event from {:#x}: {:?}", hwnd, event);
+
+                        let _ = tx.unbounded_send((hwnd, event));
+                    }
+                }
+            }
+
+            thread::sleep(Duration::from_millis(250));
+        }
+    });
+
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write};
+
+    use super::*;
+
+    fn parse(line: &str) -> Option<LogEvent> {
+        parse_line(line, &[])
+    }
+
+    #[test]
+    fn reads_vanilla_lines() {
+        assert_eq!(
+            parse("[12:34:56] [Server thread/INFO]: Steve joined the game"),
+            Some(LogEvent::WorldJoined {
+                player: "Steve".to_string()
+            })
+        );
+        assert_eq!(
+            parse("[12:40:01] [Server thread/INFO]: Stopping server"),
+            Some(LogEvent::WorldLeft)
+        );
+        assert_eq!(
+            parse(
+                "[12:35:10] [Server thread/INFO]: Preparing start region for dimension minecraft:the_nether"
+            ),
+            Some(LogEvent::Dimension("minecraft:the_nether".to_string()))
+        );
+    }
+
+    #[test]
+    fn reads_fabric_lines_without_the_colon() {
+        assert_eq!(
+            parse("[12:36:00] [Render thread/INFO] [CHAT] Steve fell from a high place"),
+            Some(LogEvent::Death {
+                player: "Steve".to_string(),
+                message: "Steve fell from a high place".to_string(),
+            })
+        );
+        assert_eq!(
+            parse(
+                "[12:39:59] [Server thread/INFO] Stopping singleplayer server as player logged out"
+            ),
+            Some(LogEvent::WorldLeft)
+        );
+    }
+
+    #[test]
+    fn reads_lines_with_the_logger_name() {
+        assert_eq!(
+            parse(
+                "[12:37:12] [Render thread/INFO] (Minecraft) [CHAT] Steve has made the advancement [We Need to Go Deeper]"
+            ),
+            Some(LogEvent::Advancement {
+                player: "Steve".to_string(),
+                advancement: "We Need to Go Deeper".to_string(),
+            })
+        );
+        assert_eq!(
+            parse("[12:37:30] [Render thread/INFO] (Minecraft): [CHAT] <Steve> gg"),
+            Some(LogEvent::Chat("<Steve> gg".to_string()))
+        );
+    }
+
+    #[test]
+    fn player_chat_is_never_a_death() {
+        assert_eq!(
+            parse("[12:38:00] [Render thread/INFO]: [CHAT] <Steve> was that a portal?"),
+            Some(LogEvent::Chat("<Steve> was that a portal?".to_string()))
+        );
+    }
+
+    #[test]
+    fn reads_a_stored_log() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/log/latest.log");
+        let events: Vec<LogEvent> = fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .filter_map(parse)
+            .collect();
+
+        let chat = |message: &str| LogEvent::Chat(message.to_string());
+        let advancement = |advancement: &str| LogEvent::Advancement {
+            player: "Steve".to_string(),
+            advancement: advancement.to_string(),
+        };
+        assert_eq!(
+            events,
+            [
+                LogEvent::Dimension("minecraft:overworld".to_string()),
+                LogEvent::WorldJoined {
+                    player: "Steve".to_string()
+                },
+                chat("<Steve> was that a portal?"),
+                advancement("Stone Age"),
+                chat("<Alex> was fun"),
+                LogEvent::Dimension("minecraft:the_nether".to_string()),
+                advancement("We Need to Go Deeper"),
+                LogEvent::Death {
+                    player: "Steve".to_string(),
+                    message: "Steve was slain by Piglin".to_string(),
+                },
+                chat("<Steve> gg"),
+                LogEvent::WorldLeft,
+                LogEvent::WorldLeft,
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_other_lines() {
+        assert_eq!(
+            parse("[12:34:50] [Render thread/WARN]: Missing sound for event"),
+            None
+        );
+        assert_eq!(
+            parse("\tat net.minecraft.client.main.Main.main(Main.java:1)"),
+            None
+        );
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn matches_custom_patterns_on_the_whole_line() {
+        let custom = compile(&[
+            LogPattern {
+                name: "seed".to_string(),
+                regex: r"\[SeedQueue\] Loaded seed (-?\d+)(?: in (\d+)ms)?".to_string(),
+            },
+            LogPattern {
+                name: "broken".to_string(),
+                regex: "(".to_string(),
+            },
+            LogPattern {
+                name: "joined".to_string(),
+                regex: "joined the game".to_string(),
+            },
+        ]);
+        assert_eq!(custom.len(), 2, "invalid patterns are skipped");
+
+        assert_eq!(
+            parse_line(
+                "[12:00:00] [Worker-Main-3/INFO]: [SeedQueue] Loaded seed -42 in 310ms",
+                &custom
+            ),
+            Some(LogEvent::Custom {
+                name: "seed".to_string(),
+                groups: vec!["-42".to_string(), "310".to_string()],
+            })
+        );
+        // Groups that didn't take part in the match are empty.
+        assert_eq!(
+            parse_line("[SeedQueue] Loaded seed 7", &custom),
+            Some(LogEvent::Custom {
+                name: "seed".to_string(),
+                groups: vec!["7".to_string(), String::new()],
+            })
+        );
+        // Built-in formats come first.
+        assert_eq!(
+            parse_line(
+                "[12:34:56] [Server thread/INFO]: Alex joined the game",
+                &custom
+            ),
+            Some(LogEvent::WorldJoined {
+                player: "Alex".to_string()
+            })
+        );
+    }
+
+    /// A log file of its own for each test, removed when done.
+    struct TempLog(PathBuf);
+
+    impl TempLog {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("wrinkle-{}-{}.log", name, std::process::id()));
+            let _ = fs::remove_file(&path);
+            Self(path)
+        }
+
+        fn append(&self, text: &str) {
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.0)
+                .unwrap()
+                .write_all(text.as_bytes())
+                .unwrap();
+        }
+    }
+
+    impl Drop for TempLog {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn tail_reads_only_new_complete_lines() {
+        let log = TempLog::new("new-lines");
+        log.append("old line\n");
+
+        let mut tail = Tail::new(log.0.clone());
+        assert!(tail.read_lines().is_empty());
+
+        log.append("first\r\nsecond\nthi");
+        assert_eq!(tail.read_lines(), ["first", "second"]);
+        assert!(tail.read_lines().is_empty());
+
+        log.append("rd\n");
+        assert_eq!(tail.read_lines(), ["third"]);
+    }
+
+    #[test]
+    fn tail_starts_over_when_truncated() {
+        let log = TempLog::new("truncated");
+        log.append("a long line from the last session\n");
+
+        let mut tail = Tail::new(log.0.clone());
+        fs::write(&log.0, "new\n").unwrap();
+
+        assert_eq!(tail.read_lines(), ["new"]);
+    }
+
+    #[test]
+    fn tail_waits_for_the_file() {
+        let log = TempLog::new("missing");
+
+        let mut tail = Tail::new(log.0.clone());
+        assert!(tail.read_lines().is_empty());
+
+        log.append("created\n");
+        assert_eq!(tail.read_lines(), ["created"]);
+
+        fs::remove_file(&log.0).unwrap();
+        assert!(tail.read_lines().is_empty());
+        // Shorter than before, as Windows may carry the creation time over
+        // to a file recreated under the same name.
+        log.append("new\n");
+        assert_eq!(tail.read_lines(), ["new"]);
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+use thiserror::Error;
+use windows::Win32::{
+    Foundation::HWND,
+    UI::{
+        Input::KeyboardAndMouse::{
+            INPUT, INPUT_0, INPUT_KEYBOARD, KEYBD_EVENT_FLAGS, KEYBDINPUT, KEYEVENTF_EXTENDEDKEY,
+            KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE, SendInput,
+        },
+        WindowsAndMessaging::GetForegroundWindow,
+    },
+};
+
+use crate::{
+    config::macros::{Key, MacroStep},
+    manager::event::{Event, EventBus},
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MacroError {
+    #[error("target window lost focus")]
+    LostFocus,
+
+    #[error("failed to inject {0}")]
+    Injection(Key),
+}
+
+/// Where macro key presses go.
+pub trait KeyInjector {
+    /// Whether key presses would currently reach the intended window.
+    fn is_focused(&self) -> bool;
+
+    fn press(&mut self, key: Key) -> Result<(), MacroError>;
+
+    fn release(&mut self, key: Key) -> Result<(), MacroError>;
+
+    fn sleep(&mut self, duration: Duration);
+}
+
+/// Injects keys with `SendInput`, as long as `hwnd` is the foreground window.
+pub struct SendInputInjector {
+    hwnd: HWND,
+}
+
+impl SendInputInjector {
+    fn send(&self, key: Key, mut flags: KEYBD_EVENT_FLAGS) -> Result<(), MacroError> {
+        flags |= KEYEVENTF_SCANCODE;
+        if key.extended {
+            flags |= KEYEVENTF_EXTENDEDKEY;
+        }
+
+        let input = INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wScan: key.scan_code,
+                    dwFlags: flags,
+                    ..KEYBDINPUT::default()
+                },
+            },
+        };
+
+        let sent = unsafe { SendInput(&[input], std::mem::size_of::<INPUT>() as i32) };
+        if sent != 1 {
+            return Err(MacroError::Injection(key));
+        }
+
+        Ok(())
+    }
+}
+
+impl KeyInjector for SendInputInjector {
+    fn is_focused(&self) -> bool {
+        unsafe { GetForegroundWindow() == self.hwnd }
+    }
+
+    fn press(&mut self, key: Key) -> Result<(), MacroError> {
+        self.send(key, KEYBD_EVENT_FLAGS::default())
+    }
+
+    fn release(&mut self, key: Key) -> Result<(), MacroError> {
+        self.send(key, KEYEVENTF_KEYUP)
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Plays `steps` into `injector`, stopping as soon as the target loses
+/// focus. Keys still held when the macro ends or stops are released, so a
+/// failed macro never leaves a key stuck down.
+pub fn play(injector: &mut impl KeyInjector, steps: &[MacroStep]) -> Result<(), MacroError> {
+    let mut held: Vec<Key> = Vec::new();
+
+    let result = steps.iter().try_for_each(|&step| {
+        if !injector.is_focused() {
+            return Err(MacroError::LostFocus);
+        }
+
+        match step {
+            MacroStep::Tap(key) => {
+                injector.press(key)?;
+                injector.release(key)
+            }
+            MacroStep::Press(key) => {
+                injector.press(key)?;
+                held.push(key);
+                Ok(())
+            }
+            MacroStep::Release(key) => {
+                held.retain(|&held| held != key);
+                injector.release(key)
+            }
+            MacroStep::Delay(ms) => {
+                injector.sleep(Duration::from_millis(ms));
+                Ok(())
+            }
+        }
+    });
+
+    for key in held.into_iter().rev() {
+        let _ = injector.release(key);
+    }
+
+    result
+}
+
+struct Job {
+    name: String,
+    steps: Vec<MacroStep>,
+    hwnd: HWND,
+}
+
+unsafe impl Send for Job {}
+
+/// Plays macros one at a time on a background thread, so that overlapping
+/// macros can't interleave their key presses.
+#[derive(Clone, Debug)]
+pub struct MacroPlayer {
+    tx: Sender<Job>,
+}
+
+impl MacroPlayer {
+    pub fn spawn(events: EventBus) -> Self {
+        let (tx, rx): (Sender<Job>, Receiver<Job>) = mpsc::channel();
+
+        thread::spawn(move || {
+            for Job { name, steps, hwnd } in rx {
+                log::debug!("Playing macro {}", name);
+
+                let mut injector = SendInputInjector { hwnd };
+                if let Err(e) = play(&mut injector, &steps) {
+                    let message = format!("Macro {} stopped: {}", name, e);
+
+                    log::warn!("{}", message);
+                    events.publish(Event::ErrorRaised(message));
+                }
+            }
+        });
+
+        Self { tx }
+    }
+
+    pub fn play(&self, name: String, steps: Vec<MacroStep>, hwnd: HWND) {
+        let _ = self.tx.send(Job { name, steps, hwnd });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Action {
+        Press(Key),
+        Release(Key),
+        Sleep(u64),
+    }
+
+    /// Records what a macro does, losing focus after `focused` checks and
+    /// failing to press `broken`.
+    struct MockInjector {
+        focused: Cell<usize>,
+        broken: Option<Key>,
+        actions: Vec<Action>,
+    }
+
+    impl MockInjector {
+        fn new() -> Self {
+            Self {
+                focused: Cell::new(usize::MAX),
+                broken: None,
+                actions: Vec::new(),
+            }
+        }
+    }
+
+    impl KeyInjector for MockInjector {
+        fn is_focused(&self) -> bool {
+            let left = self.focused.get();
+            self.focused.set(left.saturating_sub(1));
+            left > 0
+        }
+
+        fn press(&mut self, key: Key) -> Result<(), MacroError> {
+            if self.broken == Some(key) {
+                return Err(MacroError::Injection(key));
+            }
+            self.actions.push(Action::Press(key));
+            Ok(())
+        }
+
+        fn release(&mut self, key: Key) -> Result<(), MacroError> {
+            self.actions.push(Action::Release(key));
+            Ok(())
+        }
+
+        fn sleep(&mut self, duration: Duration) {
+            self.actions
+                .push(Action::Sleep(duration.as_millis() as u64));
+        }
+    }
+
+    const SHIFT: Key = Key {
+        scan_code: 0x2A,
+        extended: false,
+    };
+    const CTRL: Key = Key {
+        scan_code: 0x1D,
+        extended: false,
+    };
+    const F3: Key = Key {
+        scan_code: 0x3D,
+        extended: false,
+    };
+    const W: Key = Key {
+        scan_code: 0x11,
+        extended: false,
+    };
+
+    #[test]
+    fn plays_steps_in_order() {
+        let mut injector = MockInjector::new();
+        let steps = [
+            MacroStep::Press(SHIFT),
+            MacroStep::Tap(F3),
+            MacroStep::Release(SHIFT),
+            MacroStep::Delay(50),
+        ];
+
+        assert_eq!(play(&mut injector, &steps), Ok(()));
+        assert_eq!(
+            injector.actions,
+            [
+                Action::Press(SHIFT),
+                Action::Press(F3),
+                Action::Release(F3),
+                Action::Release(SHIFT),
+                Action::Sleep(50),
+            ]
+        );
+    }
+
+    #[test]
+    fn releases_keys_held_at_the_end() {
+        let mut injector = MockInjector::new();
+        let steps = [MacroStep::Press(SHIFT), MacroStep::Press(W)];
+
+        assert_eq!(play(&mut injector, &steps), Ok(()));
+        assert_eq!(
+            injector.actions,
+            [
+                Action::Press(SHIFT),
+                Action::Press(W),
+                Action::Release(W),
+                Action::Release(SHIFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_when_focus_is_lost() {
+        let mut injector = MockInjector {
+            focused: Cell::new(2),
+            ..MockInjector::new()
+        };
+        let steps = [
+            MacroStep::Press(SHIFT),
+            MacroStep::Tap(F3),
+            MacroStep::Tap(W),
+        ];
+
+        assert_eq!(play(&mut injector, &steps), Err(MacroError::LostFocus));
+        assert_eq!(
+            injector.actions,
+            [
+                Action::Press(SHIFT),
+                Action::Press(F3),
+                Action::Release(F3),
+                Action::Release(SHIFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn does_nothing_without_focus() {
+        let mut injector = MockInjector {
+            focused: Cell::new(0),
+            ..MockInjector::new()
+        };
+
+        assert_eq!(
+            play(&mut injector, &[MacroStep::Tap(F3)]),
+            Err(MacroError::LostFocus)
+        );
+        assert!(injector.actions.is_empty());
+    }
+
+    #[test]
+    fn releases_held_keys_on_error() {
+        let mut injector = MockInjector {
+            broken: Some(F3),
+            ..MockInjector::new()
+        };
+        let steps = [
+            MacroStep::Press(SHIFT),
+            MacroStep::Press(CTRL),
+            MacroStep::Tap(F3),
+            MacroStep::Tap(W),
+        ];
+
+        assert_eq!(play(&mut injector, &steps), Err(MacroError::Injection(F3)));
+        assert_eq!(
+            injector.actions,
+            [
+                Action::Press(SHIFT),
+                Action::Press(CTRL),
+                Action::Release(CTRL),
+                Action::Release(SHIFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn released_keys_are_not_released_again() {
+        let mut injector = MockInjector {
+            broken: Some(F3),
+            ..MockInjector::new()
+        };
+        let steps = [
+            MacroStep::Press(SHIFT),
+            MacroStep::Release(SHIFT),
+            MacroStep::Press(F3),
+        ];
+
+        assert_eq!(play(&mut injector, &steps), Err(MacroError::Injection(F3)));
+        assert_eq!(
+            injector.actions,
+            [Action::Press(SHIFT), Action::Release(SHIFT)]
+        );
+    }
+}
@@ -1,14 +1,31 @@
+mod calculator;
 mod config;
+mod game_state;
+mod hooks;
 mod instance;
+mod journal;
 mod keylogger;
+mod log_tail;
+mod macros;
 mod manager;
+mod mode;
+mod mouse;
+mod options;
+mod process;
 mod projector;
+mod script;
 mod utils;
+mod watch;
 mod window;
 mod wnd_class;
 
 fn main() {
     env_logger::init();
 
+    if std::env::args().nth(1).as_deref() == Some("restore") {
+        journal::replay(&config::Config::load_from_file().detection);
+        return;
+    }
+
     window::spawn();
 }
@@ -1,94 +1,141 @@
-use std::sync::Arc;
+pub mod event;
 
-use arc_swap::{ArcSwap, ArcSwapOption};
-use futures::{SinkExt, StreamExt};
-use futures_channel::{mpsc, oneshot};
+use std::{collections::BTreeMap, sync::Arc};
+
+use arc_swap::ArcSwap;
+use futures::StreamExt;
+use futures_channel::mpsc;
 use windows::{
-    Win32::UI::WindowsAndMessaging::{
-        DispatchMessageW, GetMessageW, MSG, SPI_GETMOUSESPEED, SPI_SETMOUSESPEED,
-        SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SetProcessDPIAware, SystemParametersInfoW,
-        TranslateMessage,
+    Win32::{
+        Foundation::HWND,
+        UI::WindowsAndMessaging::{
+            DispatchMessageW, GetMessageW, MSG, SetProcessDPIAware, TranslateMessage,
+        },
     },
     core::BOOL,
 };
 
 use crate::{
-    config::{Config, Hotkey, xy::XY},
-    instance::MinecraftInstance,
-    projector::Projector,
+    config::{Binding, Config, Hotkey, xy::XY},
+    game_state::{self, GameState},
+    hooks,
+    instance::{
+        MinecraftInstance,
+        registry::{self, InstanceRegistry},
+    },
+    journal,
+    macros::MacroPlayer,
+    manager::event::{Event, EventBus},
+    mode::ModeStack,
+    mouse::Mouse,
+    projector::Projectors,
+    script,
 };
 use crate::{
-    instance::MinecraftInstanceListener,
+    instance::{ListenerEvent, MinecraftInstanceListener},
     keylogger::{KeyEvent, KeyLogger},
+    log_tail::{self, LogEvent},
 };
 
+/// Requests to the manager from outside the input loop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    SetMode(Option<Hotkey>),
+    /// The window with this handle was closed, or no longer looks like an
+    /// instance.
+    Detach(isize),
+}
+
 pub struct Manager {
-    pub instance: Arc<ArcSwapOption<MinecraftInstance>>,
-    pub projector: Projector,
+    pub instances: InstanceRegistry,
+    pub events: EventBus,
     pub key_channel: mpsc::Receiver<KeyEvent>,
+    pub commands: mpsc::UnboundedReceiver<Command>,
+    pub game_state_changes: mpsc::UnboundedReceiver<(isize, GameState)>,
+    pub log_events: mpsc::UnboundedReceiver<(isize, LogEvent)>,
+    /// Last reported game state of each instance, by window handle.
+    pub game_states: BTreeMap<isize, GameState>,
     pub config: Arc<ArcSwap<Config>>,
-    pub state: Option<Hotkey>,
-    mouse_speed: i32,
+    /// Mode history of each instance, by window handle.
+    pub modes: BTreeMap<isize, ModeStack>,
+    mouse: Mouse,
+    macros: MacroPlayer,
 }
 
 impl Drop for Manager {
     fn drop(&mut self) {
-        self.slow_mouse(false);
-    }
-}
+        self.mouse.restore();
 
-impl Manager {
-    fn slow_mouse(&self, slow: bool) {
-        unsafe {
-            if slow {
-                SystemParametersInfoW(
-                    SPI_SETMOUSESPEED,
-                    0,
-                    Some(1 as *mut _),
-                    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS::default(),
-                )
-                .unwrap();
-            } else {
-                SystemParametersInfoW(
-                    SPI_SETMOUSESPEED,
-                    0,
-                    Some(self.mouse_speed as usize as *mut _),
-                    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS::default(),
-                )
-                .unwrap();
-            }
+        for instance in self.instances.all() {
+            instance.restore();
         }
     }
+}
 
-    pub async fn spawn(config: Arc<ArcSwap<Config>>) -> Self {
+impl Manager {
+    pub async fn spawn(config: Arc<ArcSwap<Config>>, events: EventBus) -> Self {
         unsafe {
             SetProcessDPIAware().unwrap();
         }
 
+        journal::replay(&config.load().detection);
+
         let (tx, rx) = mpsc::channel(100);
-        let (projector_tx, projector) = oneshot::channel();
-        let instance = Arc::new(ArcSwapOption::empty());
+        let (command_tx, commands) = mpsc::unbounded();
+        let instances = InstanceRegistry::default();
+        let game_state_changes = game_state::spawn(config.clone(), instances.clone());
+        let log_events = log_tail::spawn(config.clone(), instances.clone());
+
+        script::spawn(
+            config.clone(),
+            instances.clone(),
+            events.clone(),
+            command_tx.clone(),
+        );
 
         std::thread::spawn({
             let config = config.clone();
-            let instance = instance.clone();
+            let instances = instances.clone();
+            let events = events.clone();
             move || {
-                MinecraftInstanceListener::spawn({
-                    let instance = instance.clone();
-                    Box::new(move |hwnd| {
-                        log::info!("Received new minecraft instance: {:?}", hwnd);
+                MinecraftInstanceListener::spawn(config.clone(), {
+                    let config = config.clone();
+                    let instances = instances.clone();
+                    let events = events.clone();
+                    Box::new(move |ev: ListenerEvent| match ev {
+                        ListenerEvent::Found(found_instance) => {
+                            if !instances.insert(found_instance) {
+                                return;
+                            }
 
-                        let found_instance = MinecraftInstance::new(hwnd);
+                            log::info!(
+                                "Received new minecraft instance: {:?}",
+                                found_instance.hwnd
+                            );
 
-                        let rect = found_instance.get_monitor_info();
-                        found_instance.set_window_pos(rect);
+                            let config = config.load();
+                            if let Err(e) =
+                                found_instance.set_borderless(config.borderless.applies(None))
+                            {
+                                log::warn!("Failed to restyle new instance: {}", e);
+                            }
+                            let rect = found_instance.get_normal_rect(config.normal);
+                            if let Err(e) = found_instance.set_window_pos(rect) {
+                                log::warn!("Failed to resize new instance: {}", e);
+                            }
 
-                        instance.store(Some(Arc::new(found_instance)));
+                            events.publish(Event::InstanceAttached {
+                                instance: found_instance,
+                                metadata: instances
+                                    .metadata(found_instance.hwnd)
+                                    .unwrap_or_default(),
+                            });
+                        }
+                        ListenerEvent::Closed(hwnd) => {
+                            let _ = command_tx.unbounded_send(Command::Detach(registry::key(hwnd)));
+                        }
                     })
                 });
-                projector_tx
-                    .send(Projector::spawn(instance, config))
-                    .unwrap();
                 let _ = KeyLogger::spawn(tx);
 
                 let mut msg = MSG::default();
@@ -109,107 +156,284 @@ impl Manager {
             }
         });
 
-        let projector = projector.await.unwrap();
-
-        let mouse_speed = unsafe {
-            let mut mouse_speed = 0i32;
-
-            SystemParametersInfoW(
-                SPI_GETMOUSESPEED,
-                0,
-                Some(&raw mut mouse_speed as *mut _),
-                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS::default(),
-            )
-            .unwrap();
-
-            mouse_speed
-        };
+        // Projectors follow mode changes through the events.
+        let _ = Projectors::spawn(instances.clone(), config.clone(), events.clone());
 
         Manager {
             key_channel: rx,
-            projector,
-            instance,
-            state: None,
+            commands,
+            game_state_changes,
+            log_events,
+            game_states: BTreeMap::new(),
+            macros: MacroPlayer::spawn(events.clone()),
+            events,
+            instances,
+            modes: BTreeMap::new(),
             config,
-            mouse_speed,
+            mouse: Mouse::new(),
         }
     }
 
-    fn update_state(&mut self, hotkey: Hotkey) {
-        let config = *self.config.as_ref().load_full();
-        let Some(instance) = self.instance.load_full() else {
+    fn update_state(&mut self, instance: MinecraftInstance, binding: Binding) {
+        let transition = self.config.load().transition;
+
+        self.change_mode(instance, |modes| match binding {
+            Binding::Mode(hotkey) => modes.toggle(transition, hotkey),
+            Binding::Back => modes.back(),
+            Binding::Reset => modes.reset(),
+            Binding::Macro(_) => {}
+        });
+    }
+
+    fn play_macros<'a>(
+        &self,
+        config: &Config,
+        instance: &MinecraftInstance,
+        names: impl IntoIterator<Item = &'a String>,
+    ) {
+        for name in names {
+            match config.find_macro(name) {
+                Some(m) => self
+                    .macros
+                    .play(m.name.clone(), m.steps.clone(), instance.hwnd),
+                None => {
+                    let message = format!("Unknown macro: {}", name);
+
+                    log::warn!("{}", message);
+                    self.events.publish(Event::ErrorRaised(message));
+                }
+            }
+        }
+    }
+
+    fn change_mode(&mut self, instance: MinecraftInstance, f: impl FnOnce(&mut ModeStack)) {
+        let config = self.config.load_full();
+        if !instance.is_alive() {
+            self.detach(instance.hwnd);
             return;
-        };
+        }
+
+        let modes = self.modes.entry(registry::key(instance.hwnd)).or_default();
+        let before = modes.clone();
+
+        let previous = modes.current();
+        f(modes);
+        let state = modes.current();
+
+        if state == previous {
+            return;
+        }
+
+        self.instances.set_active(instance);
 
         let (position, size) = instance.get_monitor_info();
 
-        let rect = match hotkey {
-            _ if self.state == Some(hotkey) => {
+        let rect = match state {
+            None => {
                 log::debug!("Setting normal");
 
-                self.state = None;
-
-                (position, size)
+                instance.get_normal_rect(config.normal)
             }
-            Hotkey::Tall => {
+            Some(Hotkey::Tall) => {
                 log::debug!("Setting tall");
 
-                let Config { tall, .. } = config;
-
-                self.state = Some(Hotkey::Tall);
+                let tall = config.tall;
 
                 ((position + size - tall) / 2, tall)
             }
-            Hotkey::Thin => {
+            Some(Hotkey::Thin) => {
                 log::debug!("Setting thin");
 
-                let Config { thin, .. } = config;
-
-                self.state = Some(Hotkey::Thin);
+                let thin = config.thin;
 
                 ((position + size - thin) / 2, thin)
             }
-            Hotkey::Wide => {
+            Some(Hotkey::Wide) => {
                 log::debug!("Setting wide");
 
-                let Config { wide, .. } = config;
-
-                self.state = Some(Hotkey::Wide);
+                let wide = config.wide;
 
                 ((position + size - wide) / 2, wide)
             }
         };
 
-        instance.set_window_pos(rect);
+        if let Err(e) = instance.set_borderless(config.borderless.applies(state)) {
+            log::warn!("Failed to restyle instance: {}", e);
+        }
+        if let Err(e) = instance.set_window_pos(rect) {
+            self.modes.insert(registry::key(instance.hwnd), before);
+            self.events.publish(Event::ErrorRaised(format!(
+                "Failed to resize instance: {}",
+                e
+            )));
+            return;
+        }
+
+        if let Some(previous) = previous {
+            self.play_macros(
+                &config,
+                &instance,
+                &config.mode_macros.get(previous).on_exit,
+            );
+        }
+        if let Some(state) = state {
+            self.play_macros(&config, &instance, &config.mode_macros.get(state).on_enter);
+        }
 
-        self.projector.hotkey_hook(self.state);
-        self.slow_mouse(self.state == Some(Hotkey::Tall));
+        if config.hooks_enabled {
+            hooks::spawn(&config.hooks, previous, state, rect.1, self.events.clone());
+        }
+
+        self.events.publish(Event::ModeChanged {
+            instance,
+            from: previous,
+            to: state,
+        });
+        self.mouse.apply(
+            state
+                .map(|hotkey| *config.mouse.get(hotkey))
+                .unwrap_or_default(),
+        );
     }
 
-    pub async fn run(&mut self, mut tx: mpsc::Sender<KeyEvent>) {
-        while let Some(ev) = self.key_channel.next().await {
-            let _ = tx.send(ev).await;
+    /// Drops everything known about a closed instance. The window is gone,
+    /// so there's nothing to restore, but if it was the active instance,
+    /// anything depending on its mode is told about the mode of the instance
+    /// active now.
+    fn detach(&mut self, hwnd: HWND) {
+        // Removing the active instance makes another one active.
+        let was_active = self
+            .instances
+            .active()
+            .is_some_and(|active| active.hwnd == hwnd);
+        let Some(instance) = self.instances.remove(hwnd) else {
+            return;
+        };
+
+        log::info!("Detaching minecraft instance: {:?}", hwnd);
 
-            let Some(instance) = self.instance.load_full() else {
-                continue;
+        instance.forget();
+
+        let key = registry::key(hwnd);
+        self.game_states.remove(&key);
+        let from = self.modes.remove(&key).and_then(|modes| modes.current());
+        if was_active {
+            let active = self.instances.active();
+            let to = active
+                .and_then(|active| self.modes.get(&registry::key(active.hwnd)))
+                .and_then(ModeStack::current);
+
+            if from != to {
+                self.events.publish(Event::ModeChanged {
+                    instance: active.unwrap_or(instance),
+                    from,
+                    to,
+                });
+                self.mouse.apply(
+                    to.map(|hotkey| *self.config.load().mouse.get(hotkey))
+                        .unwrap_or_default(),
+                );
+            }
+        }
+
+        self.events.publish(Event::InstanceDetached(instance));
+    }
+
+    fn run_command(&mut self, command: Command) {
+        log::debug!("Running command: {:?}", command);
+
+        match command {
+            Command::SetMode(mode) => {
+                if let Some(instance) = self.instances.active() {
+                    self.change_mode(instance, |modes| modes.set(mode));
+                }
+            }
+            Command::Detach(hwnd) => self.detach(HWND(hwnd as *mut _)),
+        }
+    }
+
+    fn game_state(&self, instance: MinecraftInstance) -> GameState {
+        self.game_states
+            .get(&registry::key(instance.hwnd))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn set_game_state(&mut self, hwnd: isize, state: GameState) {
+        let Some(instance) = self.instances.get(HWND(hwnd as *mut _)) else {
+            return;
+        };
+
+        log::debug!("Game state of {:?}: {}", instance.hwnd, state);
+
+        let left_world = self.game_state(instance).in_world() && !state.in_world();
+        self.game_states.insert(hwnd, state);
+        self.events
+            .publish(Event::GameStateChanged { instance, state });
+
+        if left_world && self.config.load().reset_outside_world {
+            self.change_mode(instance, ModeStack::reset);
+        }
+    }
+
+    pub async fn run(&mut self) {
+        loop {
+            let ev = futures::select! {
+                ev = self.key_channel.next() => ev,
+                command = self.commands.next() => {
+                    if let Some(command) = command {
+                        self.run_command(command);
+                    }
+                    continue;
+                }
+                log_event = self.log_events.next() => {
+                    if let Some((hwnd, event)) = log_event
+                        && let Some(instance) = self.instances.get(HWND(hwnd as *mut _))
+                    {
+                        self.events.publish(Event::Log { instance, event });
+                    }
+                    continue;
+                }
+                change = self.game_state_changes.next() => {
+                    if let Some((hwnd, state)) = change {
+                        self.set_game_state(hwnd, state);
+                    }
+                    continue;
+                }
+            };
+            let Some(ev) = ev else {
+                break;
             };
-            if !instance.is_foreground() {
+
+            self.events.publish(Event::KeyPressed(ev));
+
+            let Some(instance) = self.instances.foreground() else {
                 continue;
-            }
+            };
 
-            let config = **self.config.as_ref().load();
-            if let Some(tall) = config.tall_key
-                && tall.test(ev)
-            {
-                self.update_state(Hotkey::Tall);
-            } else if let Some(thin) = config.thin_key
-                && thin.test(ev)
-            {
-                self.update_state(Hotkey::Thin);
-            } else if let Some(wide) = config.wide_key
-                && wide.test(ev)
+            let config = self.config.load_full();
+            if let Some(binding) = Binding::ALL
+                .into_iter()
+                .chain((0..config.macros.len()).map(Binding::Macro))
+                .find(|&binding| config.get_key(binding).is_some_and(|key| key.test(ev)))
             {
-                self.update_state(Hotkey::Wide);
+                self.events.publish(Event::HotkeyTriggered(binding));
+
+                match binding {
+                    Binding::Mode(_)
+                        if config.require_in_world
+                            && self.game_state(instance) != GameState::Unknown
+                            && !self.game_state(instance).in_world() =>
+                    {
+                        log::debug!("Ignoring {} outside of a world", binding.name());
+                    }
+                    Binding::Macro(i) => {
+                        let m = &config.macros[i];
+                        self.macros
+                            .play(m.name.clone(), m.steps.clone(), instance.hwnd);
+                    }
+                    binding => self.update_state(instance, binding),
+                }
             }
         }
     }
@@ -0,0 +1,82 @@
+use std::sync::{Arc, Mutex};
+
+use futures_channel::mpsc;
+
+use crate::{
+    config::{Binding, Hotkey},
+    game_state::GameState,
+    instance::{MinecraftInstance, metadata::InstanceMetadata},
+    keylogger::KeyEvent,
+    log_tail::LogEvent,
+    projector::{eye::EyeReading, pacing::FrameStats, pie::PieChart},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    KeyPressed(KeyEvent),
+    HotkeyTriggered(Binding),
+    /// The mode of the active instance changed, or another instance with a
+    /// different mode became active.
+    ModeChanged {
+        instance: MinecraftInstance,
+        from: Option<Hotkey>,
+        to: Option<Hotkey>,
+    },
+    InstanceAttached {
+        instance: MinecraftInstance,
+        /// Resolved when the instance was registered, so listeners don't
+        /// have to open its process again.
+        metadata: Arc<InstanceMetadata>,
+    },
+    InstanceDetached(MinecraftInstance),
+    GameStateChanged {
+        instance: MinecraftInstance,
+        state: GameState,
+    },
+    Log {
+        instance: MinecraftInstance,
+        event: LogEvent,
+    },
+    /// Frame statistics of the named projector over the last second,
+    /// all zero once it's hidden.
+    ProjectorStats {
+        name: String,
+        stats: FrameStats,
+    },
+    /// The pie chart last read from the active instance, None once there's
+    /// no chart to read.
+    PieChartRead(Option<PieChart>),
+    /// The ender eye last found in tall mode, None once it's gone.
+    EyeDetected(Option<EyeReading>),
+    ConfigReloaded,
+    ErrorRaised(String),
+}
+
+/// Broadcasts every published [`Event`] to all current subscribers.
+#[derive(Clone, Debug, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<mpsc::UnboundedSender<Event>>>>,
+}
+
+impl std::hash::Hash for EventBus {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.subscribers).hash(state);
+    }
+}
+
+impl EventBus {
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<Event> {
+        let (tx, rx) = mpsc::unbounded();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    pub fn publish(&self, event: Event) {
+        log::trace!("Publishing event: {:?}", event);
+
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|tx| tx.unbounded_send(event.clone()).is_ok());
+    }
+}
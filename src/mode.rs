@@ -0,0 +1,171 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Hotkey;
+
+/// What pressing a mode's key does while another mode is active.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transition {
+    /// Pressing the active mode's key drops straight back to normal.
+    #[default]
+    Toggle,
+    /// Pressing the active mode's key returns to the mode that was active
+    /// before it.
+    Stack,
+}
+
+impl Transition {
+    pub const ALL: [Transition; 2] = [Transition::Toggle, Transition::Stack];
+}
+
+impl Display for Transition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Transition::Toggle => f.write_str("Toggle"),
+            Transition::Stack => f.write_str("Stack"),
+        }
+    }
+}
+
+/// History of entered modes, the last one being the active mode. An empty
+/// stack means normal.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModeStack {
+    modes: Vec<Hotkey>,
+}
+
+impl ModeStack {
+    pub fn current(&self) -> Option<Hotkey> {
+        self.modes.last().copied()
+    }
+
+    pub fn toggle(&mut self, transition: Transition, hotkey: Hotkey) {
+        if self.current() == Some(hotkey) {
+            match transition {
+                Transition::Toggle => self.reset(),
+                Transition::Stack => self.back(),
+            }
+
+            return;
+        }
+
+        self.push(hotkey);
+    }
+
+    /// Enters `mode` directly, regardless of the transition policy.
+    pub fn set(&mut self, mode: Option<Hotkey>) {
+        match mode {
+            None => self.reset(),
+            Some(hotkey) if self.current() != Some(hotkey) => self.push(hotkey),
+            Some(_) => {}
+        }
+    }
+
+    /// Makes `hotkey` the active mode, moving it up if it was entered before
+    /// so each mode is on the stack once.
+    fn push(&mut self, hotkey: Hotkey) {
+        self.modes.retain(|&mode| mode != hotkey);
+        self.modes.push(hotkey);
+    }
+
+    pub fn back(&mut self) {
+        self.modes.pop();
+    }
+
+    pub fn reset(&mut self) {
+        self.modes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entered(transition: Transition, hotkeys: &[Hotkey]) -> ModeStack {
+        let mut stack = ModeStack::default();
+        for &hotkey in hotkeys {
+            stack.toggle(transition, hotkey);
+        }
+        stack
+    }
+
+    #[test]
+    fn starts_in_normal() {
+        assert_eq!(ModeStack::default().current(), None);
+    }
+
+    #[test]
+    fn pressing_the_active_mode_again() {
+        let keys = [Hotkey::Wide, Hotkey::Tall, Hotkey::Tall];
+
+        assert_eq!(
+            entered(Transition::Stack, &keys).current(),
+            Some(Hotkey::Wide)
+        );
+        assert_eq!(entered(Transition::Toggle, &keys).current(), None);
+    }
+
+    #[test]
+    fn pressing_another_mode_enters_it() {
+        for transition in Transition::ALL {
+            let stack = entered(transition, &[Hotkey::Wide, Hotkey::Tall]);
+            assert_eq!(stack.current(), Some(Hotkey::Tall));
+        }
+    }
+
+    #[test]
+    fn modes_are_stacked_once() {
+        let mut stack = entered(
+            Transition::Stack,
+            &[Hotkey::Wide, Hotkey::Tall, Hotkey::Wide],
+        );
+
+        assert_eq!(stack.current(), Some(Hotkey::Wide));
+        stack.back();
+        assert_eq!(stack.current(), Some(Hotkey::Tall));
+        stack.back();
+        assert_eq!(stack.current(), None);
+    }
+
+    #[test]
+    fn back_returns_to_the_previous_mode() {
+        let mut stack = entered(Transition::Toggle, &[Hotkey::Thin, Hotkey::Tall]);
+
+        stack.back();
+        assert_eq!(stack.current(), Some(Hotkey::Thin));
+        stack.back();
+        assert_eq!(stack.current(), None);
+        stack.back();
+        assert_eq!(stack.current(), None);
+    }
+
+    #[test]
+    fn reset_returns_to_normal() {
+        let mut stack = entered(
+            Transition::Stack,
+            &[Hotkey::Thin, Hotkey::Tall, Hotkey::Wide],
+        );
+
+        stack.reset();
+        assert_eq!(stack, ModeStack::default());
+    }
+
+    #[test]
+    fn set_enters_a_mode_directly() {
+        let mut stack = ModeStack::default();
+
+        stack.set(Some(Hotkey::Tall));
+        stack.set(Some(Hotkey::Tall));
+        assert_eq!(stack.current(), Some(Hotkey::Tall));
+
+        stack.set(Some(Hotkey::Wide));
+        assert_eq!(stack.current(), Some(Hotkey::Wide));
+        stack.back();
+        assert_eq!(stack.current(), Some(Hotkey::Tall));
+
+        stack.set(None);
+        assert_eq!(stack.current(), None);
+    }
+}
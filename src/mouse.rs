@@ -0,0 +1,136 @@
+use std::ffi::c_void;
+
+use serde::{Deserialize, Serialize};
+use windows::Win32::UI::WindowsAndMessaging::{
+    SPI_GETMOUSE, SPI_GETMOUSESPEED, SPI_SETMOUSE, SPI_SETMOUSESPEED,
+    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SystemParametersInfoW,
+};
+
+use crate::{
+    config::mouse::MouseSettings,
+    journal::{self, Entry},
+};
+
+/// Thresholds Windows uses when "Enhance pointer precision" is turned on
+/// from the control panel.
+const DEFAULT_THRESHOLDS: [i32; 2] = [6, 10];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MouseState {
+    pub speed: i32,
+    /// `[threshold1, threshold2, acceleration]` as used by `SPI_GETMOUSE`.
+    pub acceleration: [i32; 3],
+}
+
+impl MouseState {
+    pub fn read() -> Self {
+        unsafe {
+            let mut speed = 0i32;
+            SystemParametersInfoW(
+                SPI_GETMOUSESPEED,
+                0,
+                Some(&raw mut speed as *mut c_void),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS::default(),
+            )
+            .unwrap();
+
+            let mut acceleration = [0i32; 3];
+            SystemParametersInfoW(
+                SPI_GETMOUSE,
+                0,
+                Some(acceleration.as_mut_ptr() as *mut c_void),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS::default(),
+            )
+            .unwrap();
+
+            Self {
+                speed,
+                acceleration,
+            }
+        }
+    }
+
+    pub fn write(&self) {
+        let mut acceleration = self.acceleration;
+
+        unsafe {
+            SystemParametersInfoW(
+                SPI_SETMOUSESPEED,
+                0,
+                Some(self.speed as usize as *mut c_void),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS::default(),
+            )
+            .unwrap();
+
+            SystemParametersInfoW(
+                SPI_SETMOUSE,
+                0,
+                Some(acceleration.as_mut_ptr() as *mut c_void),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS::default(),
+            )
+            .unwrap();
+        }
+    }
+
+    fn with(self, settings: MouseSettings) -> Self {
+        let mut state = self;
+
+        if let Some(speed) = settings.speed {
+            state.speed = speed.clamp(1, 20);
+        }
+
+        match settings.enhance_pointer_precision {
+            Some(false) => {
+                state.acceleration[2] = 0;
+            }
+            Some(true) if self.acceleration[2] == 0 => {
+                let [threshold1, threshold2] = DEFAULT_THRESHOLDS;
+                state.acceleration = [threshold1, threshold2, 1];
+            }
+            _ => {}
+        }
+
+        state
+    }
+}
+
+/// Applies per-mode pointer settings on top of the user's own, and puts the
+/// user's settings back afterwards.
+pub struct Mouse {
+    original: MouseState,
+    current: MouseState,
+}
+
+impl Mouse {
+    pub fn new() -> Self {
+        let original = MouseState::read();
+
+        Self {
+            original,
+            current: original,
+        }
+    }
+
+    pub fn apply(&mut self, settings: MouseSettings) {
+        let state = self.original.with(settings);
+
+        if state != self.current {
+            log::debug!("Setting mouse: {:?}", state);
+
+            if state != self.original {
+                journal::record(Entry::Mouse(self.original));
+            }
+
+            state.write();
+            self.current = state;
+
+            if state == self.original {
+                journal::forget(Entry::Mouse(self.original));
+            }
+        }
+    }
+
+    pub fn restore(&mut self) {
+        self.apply(MouseSettings::default());
+    }
+}
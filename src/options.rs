@@ -0,0 +1,267 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+use futures::StreamExt;
+use windows::Win32::Foundation::HWND;
+
+use crate::{
+    config::{Config, xy::XY},
+    instance::registry::{self, InstanceRegistry},
+    watch,
+};
+
+/// The settings from the game's `options.txt` that decide how big its
+/// interface and world are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameOptions {
+    /// The chosen GUI scale, 0 being "Auto".
+    pub gui_scale: i32,
+    pub force_unicode_font: bool,
+    /// Vertical field of view in degrees.
+    pub fov: i32,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        Self {
+            gui_scale: 0,
+            force_unicode_font: false,
+            fov: 70,
+        }
+    }
+}
+
+impl GameOptions {
+    /// Reads the relevant `key:value` lines of `options.txt`. Missing or
+    /// malformed values keep their defaults.
+    pub fn parse(contents: &str) -> Self {
+        let mut options = Self::default();
+
+        for (key, value) in contents.lines().filter_map(|line| line.split_once(':')) {
+            match key {
+                "guiScale" => {
+                    if let Ok(gui_scale) = value.trim().parse() {
+                        options.gui_scale = gui_scale;
+                    }
+                }
+                "forceUnicodeFont" => {
+                    options.force_unicode_font = value.trim() == "true";
+                }
+                // Stored from -1 to 1 for the slider's 30 to 110 degrees.
+                "fov" => {
+                    if let Ok(fov) = value.trim().parse::<f64>() {
+                        options.fov = (70.0 + fov * 40.0).round() as i32;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        options
+    }
+
+    fn path(game_dir: &Path) -> PathBuf {
+        game_dir.join("options.txt")
+    }
+
+    pub fn read(game_dir: &Path) -> Self {
+        fs::read_to_string(Self::path(game_dir))
+            .map(|contents| Self::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    /// The scale the game actually draws its interface at in a window of
+    /// `size`: the chosen scale, or the largest that fits if that's too big,
+    /// rounded up to an even number with the Unicode font.
+    pub fn effective_gui_scale(self, size: XY) -> i32 {
+        let mut scale = 1;
+        while scale != self.gui_scale
+            && scale < size.x
+            && scale < size.y
+            && size.x / (scale + 1) >= 320
+            && size.y / (scale + 1) >= 240
+        {
+            scale += 1;
+        }
+
+        if self.force_unicode_font && scale % 2 != 0 {
+            scale += 1;
+        }
+
+        scale
+    }
+}
+
+/// The [`GameOptions`] of every instance, reloaded when their `options.txt`
+/// changes.
+#[derive(Clone, Debug, Default)]
+pub struct OptionsCache {
+    options: Arc<ArcSwap<BTreeMap<isize, GameOptions>>>,
+}
+
+impl OptionsCache {
+    pub fn get(&self, hwnd: HWND) -> GameOptions {
+        self.options
+            .load()
+            .get(&registry::key(hwnd))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn spawn(config: Arc<ArcSwap<Config>>, instances: InstanceRegistry) -> Self {
+        let cache = Self::default();
+
+        let paths = move || -> Vec<(isize, Option<PathBuf>)> {
+            let config = config.load();
+
+            instances
+                .all()
+                .into_iter()
+                .map(|instance| {
+                    (
+                        registry::key(instance.hwnd),
+                        instances.game_dir(instance.hwnd, &config),
+                    )
+                })
+                .collect()
+        };
+
+        let changed = watch::spawn(Duration::from_millis(500), {
+            let paths = paths.clone();
+            move || {
+                watch::fingerprint(paths().into_iter().map(|(hwnd, game_dir)| {
+                    game_dir
+                        .map(|game_dir| GameOptions::path(&game_dir))
+                        .unwrap_or_else(|| PathBuf::from(hwnd.to_string()))
+                }))
+            }
+        });
+
+        std::thread::spawn({
+            let cache = cache.clone();
+            move || {
+                let reload = || {
+                    let options = paths()
+                        .into_iter()
+                        .map(|(hwnd, game_dir)| {
+                            let options = game_dir
+                                .map(|game_dir| GameOptions::read(&game_dir))
+                                .unwrap_or_default();
+                            (hwnd, options)
+                        })
+                        .collect();
+
+                    log::debug!("Game options: {:?}", options);
+                    cache.options.store(Arc::new(options));
+                };
+
+                reload();
+                futures::executor::block_on(changed.for_each(|()| {
+                    reload();
+                    async {}
+                }));
+            }
+        });
+
+        cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(gui_scale: i32, force_unicode_font: bool) -> GameOptions {
+        GameOptions {
+            gui_scale,
+            force_unicode_font,
+            ..GameOptions::default()
+        }
+    }
+
+    #[test]
+    fn parses_options_txt() {
+        let contents = "version:3955\n\
+            fov:0.5\n\
+            guiScale:3\n\
+            forceUnicodeFont:true\n\
+            key_key.attack:key.mouse.left\n";
+
+        assert_eq!(
+            GameOptions::parse(contents),
+            GameOptions {
+                gui_scale: 3,
+                force_unicode_font: true,
+                fov: 90,
+            }
+        );
+    }
+
+    #[test]
+    fn fov_spans_the_slider() {
+        assert_eq!(GameOptions::parse("fov:-1.0").fov, 30);
+        assert_eq!(GameOptions::parse("fov:0.0").fov, 70);
+        assert_eq!(GameOptions::parse("fov:1.0").fov, 110);
+        assert_eq!(GameOptions::parse("fov:0.0125").fov, 71);
+    }
+
+    #[test]
+    fn malformed_values_keep_their_defaults() {
+        assert_eq!(GameOptions::parse(""), GameOptions::default());
+        assert_eq!(
+            GameOptions::parse("guiScale:big\nfov:wide\nforceUnicodeFont:yes"),
+            GameOptions::default()
+        );
+    }
+
+    #[test]
+    fn chosen_scales_are_kept_when_they_fit() {
+        let size = XY::new(1920, 1080);
+
+        for scale in 1..=4 {
+            assert_eq!(options(scale, false).effective_gui_scale(size), scale);
+        }
+    }
+
+    #[test]
+    fn scales_too_big_for_the_window_are_lowered() {
+        assert_eq!(
+            options(6, false).effective_gui_scale(XY::new(1920, 1080)),
+            4
+        );
+        assert_eq!(options(4, false).effective_gui_scale(XY::new(400, 1800)), 1);
+        assert_eq!(
+            options(2, false).effective_gui_scale(XY::new(384, 16384)),
+            1
+        );
+    }
+
+    #[test]
+    fn auto_picks_the_largest_that_fits() {
+        assert_eq!(
+            options(0, false).effective_gui_scale(XY::new(1920, 1080)),
+            4
+        );
+        assert_eq!(
+            options(0, false).effective_gui_scale(XY::new(2560, 1440)),
+            6
+        );
+        assert_eq!(options(0, false).effective_gui_scale(XY::new(1280, 720)), 3);
+        assert_eq!(options(0, false).effective_gui_scale(XY::new(400, 1800)), 1);
+    }
+
+    #[test]
+    fn unicode_font_rounds_up_to_even() {
+        assert_eq!(options(1, true).effective_gui_scale(XY::new(1920, 1080)), 2);
+        assert_eq!(options(3, true).effective_gui_scale(XY::new(1920, 1080)), 4);
+        assert_eq!(options(4, true).effective_gui_scale(XY::new(1920, 1080)), 4);
+        assert_eq!(options(0, true).effective_gui_scale(XY::new(1280, 720)), 4);
+        assert_eq!(options(0, true).effective_gui_scale(XY::new(400, 1800)), 2);
+    }
+}
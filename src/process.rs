@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+
+use widestring::U16Str;
+use windows::{
+    Wdk::System::Threading::{NtQueryInformationProcess, ProcessCommandLineInformation},
+    Win32::{
+        Foundation::{CloseHandle, HANDLE, HWND, UNICODE_STRING},
+        System::Threading::{
+            OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+            QueryFullProcessImageNameW,
+        },
+        UI::WindowsAndMessaging::GetWindowThreadProcessId,
+    },
+    core::PWSTR,
+};
+
+/// A process opened for querying, closed on drop.
+pub struct Process {
+    pid: u32,
+    handle: HANDLE,
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
+
+impl Process {
+    pub fn of_window(hwnd: HWND) -> Option<Self> {
+        let mut pid = 0;
+        unsafe { GetWindowThreadProcessId(hwnd, Some(&raw mut pid)) };
+        if pid == 0 {
+            return None;
+        }
+
+        let handle = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+
+        Some(Self { pid, handle })
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    pub fn executable(&self) -> Option<PathBuf> {
+        let mut buf = [0u16; 1024];
+        let mut len = buf.len() as u32;
+
+        unsafe {
+            QueryFullProcessImageNameW(
+                self.handle,
+                PROCESS_NAME_WIN32,
+                PWSTR(buf.as_mut_ptr()),
+                &raw mut len,
+            )
+            .ok()?;
+        }
+
+        Some(
+            U16Str::from_slice(&buf[..len as usize])
+                .to_os_string()
+                .into(),
+        )
+    }
+
+    /// The full command line the process was started with.
+    pub fn command_line(&self) -> Option<String> {
+        unsafe {
+            let mut len = 0;
+            let _ = NtQueryInformationProcess(
+                self.handle,
+                ProcessCommandLineInformation,
+                std::ptr::null_mut(),
+                0,
+                &raw mut len,
+            );
+            if len == 0 {
+                return None;
+            }
+
+            // Room for the `UNICODE_STRING` header and the string it points
+            // into, which follows it in the same buffer. `u64`s keep the
+            // header aligned.
+            let mut buf = vec![0u64; (len as usize).div_ceil(8)];
+            NtQueryInformationProcess(
+                self.handle,
+                ProcessCommandLineInformation,
+                buf.as_mut_ptr().cast(),
+                len,
+                &raw mut len,
+            )
+            .ok()
+            .ok()?;
+
+            let string = &*buf.as_ptr().cast::<UNICODE_STRING>();
+            if string.Buffer.is_null() {
+                return None;
+            }
+
+            let chars = std::slice::from_raw_parts(string.Buffer.0, string.Length as usize / 2);
+            Some(String::from_utf16_lossy(chars))
+        }
+    }
+}
@@ -1,43 +1,100 @@
-use arc_swap::{ArcSwap, ArcSwapOption};
-use std::sync::{Arc, LazyLock};
-use windows::Win32::Foundation::{COLORREF, FALSE, HWND, LPARAM, LRESULT, RECT, WPARAM};
+pub mod crop;
+pub mod eye;
+pub mod isolate;
+pub mod pacing;
+pub mod pie;
+pub mod placement;
+pub mod render;
+
+use arc_swap::ArcSwap;
+use futures::StreamExt;
+use std::sync::{Arc, LazyLock, mpsc};
+use std::time::{Duration, Instant};
+use widestring::U16CString;
+use windows::Win32::Foundation::{COLORREF, FALSE, HWND, LPARAM, LRESULT, RECT, TRUE, WPARAM};
 use windows::Win32::Graphics::Gdi::{
     BeginPaint, CLIP_DEFAULT_PRECIS, CreateFontW, CreateSolidBrush, DEFAULT_CHARSET,
-    DEFAULT_QUALITY, DT_CENTER, DT_NOCLIP, DT_SINGLELINE, DT_VCENTER, DeleteObject, DrawTextA,
-    EndPaint, FIXED_PITCH, FW_SEMIBOLD, FillRect, GetDC, HBRUSH, InvalidateRect, MM_TEXT,
-    OUT_DEFAULT_PRECIS, PAINTSTRUCT, ReleaseDC, SRCCOPY, SelectObject, SetBkMode, SetMapMode,
-    SetTextColor, StretchBlt, TRANSPARENT,
+    DEFAULT_QUALITY, DT_CENTER, DT_LEFT, DT_NOCLIP, DT_SINGLELINE, DT_VCENTER, DeleteObject,
+    DrawTextA, DrawTextW, EndPaint, EnumDisplayMonitors, FIXED_PITCH, FW_SEMIBOLD, FillRect, GetDC,
+    HBRUSH, HDC, HMONITOR, InvalidateRect, MM_TEXT, OUT_DEFAULT_PRECIS, PAINTSTRUCT, ReleaseDC,
+    SelectObject, SetBkMode, SetMapMode, SetTextColor, TRANSPARENT,
 };
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Threading::GetCurrentThreadId;
 use windows::Win32::UI::WindowsAndMessaging::{
-    DefWindowProcW, GWL_HWNDPARENT, GetWindowRect, RegisterClassExW, SW_HIDE, SW_SHOW,
-    SWP_FRAMECHANGED, SetWindowLongPtrW, SetWindowPos, ShowWindow, WM_PAINT, WM_SHOWWINDOW,
-    WNDCLASSEXW, WS_EX_TOPMOST, WS_POPUP,
+    DefWindowProcW, DestroyWindow, DispatchMessageW, GWL_HWNDPARENT, GetMessageW, GetWindowRect,
+    HWND_NOTOPMOST, HWND_TOPMOST, IsIconic, IsWindowVisible, MSG, PostMessageW, PostThreadMessageW,
+    RegisterClassExW, SW_HIDE, SW_SHOWNOACTIVATE, SWP_FRAMECHANGED, SWP_NOACTIVATE,
+    SetWindowLongPtrW, SetWindowPos, ShowWindow, TranslateMessage, WM_APP, WM_DISPLAYCHANGE,
+    WM_PAINT, WM_SHOWWINDOW, WNDCLASSEXW, WS_EX_TOPMOST, WS_POPUP,
 };
-use windows::core::PCWSTR;
+use windows::core::{BOOL, PCWSTR};
 
+use crate::config::color::Color;
+use crate::config::projector::{Backend, ProjectorSettings, Source};
 use crate::config::xy::XY;
 use crate::config::{Config, Hotkey};
 use crate::instance::MinecraftInstance;
+use crate::instance::registry::InstanceRegistry;
+use crate::manager::event::{Event, EventBus};
+use crate::options::OptionsCache;
+use crate::projector::crop::Region;
+use crate::projector::eye::EyeReading;
+use crate::projector::pacing::{FrameRecorder, FrameStats, Timer};
+use crate::projector::pie::PieChart;
+use crate::projector::render::{CapturingRenderer, GdiRenderer, Image};
 use crate::utils::UnsafeSync;
 use crate::wnd_class::{self, WndClass, wnd_proc};
+/// Asks a projector window to place and show itself.
+const WM_PLACE: u32 = WM_APP;
+/// Asks the projector thread to match its windows to the config.
+const WM_SYNC: u32 = WM_APP + 1;
+/// How often the pie chart and ender eye are read while a shown projector
+/// wants them.
+const READ_INTERVAL: Duration = Duration::from_millis(250);
+/// How often the scheduler checks on a minimized instance while it waits,
+/// as nothing announces one being restored.
+const RESTORE_CHECK: Duration = Duration::from_millis(250);
+
 pub struct ProjectorWindow {
-    instance: Arc<ArcSwapOption<MinecraftInstance>>,
-    hotkey: Arc<ArcSwap<Option<Hotkey>>>,
+    name: String,
+    config: Arc<ArcSwap<Config>>,
+    instances: InstanceRegistry,
+    options: OptionsCache,
+    events: EventBus,
+    pie: Arc<ArcSwap<Option<PieChart>>>,
+    recorder: FrameRecorder,
     ruler: Ruler,
     width: i32,
     height: i32,
 }
 
+/// A projector window, named after its [`ProjectorSettings`].
 #[derive(Clone, Debug)]
-pub struct Projector {
+struct Projector {
+    name: String,
     hwnd: HWND,
-    hotkey: Arc<ArcSwap<Option<Hotkey>>>,
 }
 
 unsafe impl Send for Projector {}
 unsafe impl Sync for Projector {}
 
+/// All projector windows, living on a thread of their own and shown in the
+/// modes their settings list.
+#[derive(Clone, Debug)]
+pub struct Projectors {
+    thread_id: u32,
+    mode: Arc<ArcSwap<Option<Hotkey>>>,
+    windows: Arc<ArcSwap<Vec<Projector>>>,
+    /// The windows shown in the current mode, which the scheduler repaints.
+    shown: Arc<ArcSwap<Vec<Projector>>>,
+    /// Wakes the scheduler once there are windows to repaint.
+    wake: mpsc::Sender<()>,
+    /// The pie chart last read by the scheduler, for readouts.
+    pie: Arc<ArcSwap<Option<PieChart>>>,
+    config: Arc<ArcSwap<Config>>,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Ruler {
     hwnd: HWND,
@@ -102,7 +159,7 @@ impl WndClass for RulerWindow {
                         self.white,
                     );
 
-                    let config = *self.config.load_full();
+                    let config = self.config.load_full();
 
                     let brushes = config.colors.map(|color| CreateSolidBrush(color.into()));
                     for i in -config.ruler..config.ruler {
@@ -239,134 +296,97 @@ impl WndClass for ProjectorWindow {
     fn on_message(&mut self, hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
         unsafe {
             match (msg, wparam) {
-                (WM_SHOWWINDOW, _) => {
-                    let Some(instance) = self.instance.load_full() else {
-                        return DefWindowProcW(hwnd, msg, wparam, lparam);
-                    };
+                (WM_PLACE, _) => {
+                    self.place(hwnd);
 
-                    let hotkey = *self.hotkey.load_full();
-
-                    let rect = instance.get_window_rect();
-                    let window_width = rect.right - rect.left;
-                    let (position, size) = instance.get_monitor_info();
-                    let projector_size = XY::new(
-                        (size.x - window_width) / 2,
-                        if hotkey == Some(Hotkey::Tall) {
-                            800
-                        } else {
-                            1400
-                        },
-                    );
+                    LRESULT(0)
+                }
+                (WM_DISPLAYCHANGE, _) => {
+                    if IsWindowVisible(hwnd).as_bool() {
+                        self.place(hwnd);
+                    }
 
-                    self.width = projector_size.x;
-                    self.height = projector_size.y;
-                    SetWindowPos(
-                        hwnd,
-                        None,
-                        position.x,
-                        (position.y + size.y - projector_size.y) / 2,
-                        projector_size.x,
-                        projector_size.y,
-                        SWP_FRAMECHANGED,
-                    )
-                    .unwrap();
-                    self.ruler.set_window_pos(
-                        hwnd,
-                        position.x,
-                        (position.y + size.y - projector_size.y) / 2,
-                        projector_size.x,
-                        projector_size.y,
-                    );
+                    DefWindowProcW(hwnd, msg, wparam, lparam)
+                }
+                (WM_SHOWWINDOW, WPARAM(0)) => {
+                    let _ = ShowWindow(self.ruler.hwnd, SW_HIDE);
+
+                    self.recorder = FrameRecorder::default();
+                    self.events.publish(Event::ProjectorStats {
+                        name: self.name.clone(),
+                        stats: FrameStats::default(),
+                    });
 
-                    LRESULT(1)
+                    LRESULT(0)
                 }
                 (WM_PAINT, _) => {
-                    let Some(instance) = self.instance.load_full() else {
+                    let Some(instance) = self.instances.active() else {
                         return DefWindowProcW(hwnd, msg, wparam, lparam);
                     };
 
-                    let hotkey = *self.hotkey.load_full();
-
-                    let rect = instance.get_window_rect();
-                    let width = rect.right - rect.left;
-                    let height = rect.bottom - rect.top;
-
+                    let started = Instant::now();
                     let mut ps = PAINTSTRUCT::default();
                     let source_hdc = GetDC(Some(instance.hwnd));
                     let projector_hdc = BeginPaint(hwnd, &raw mut ps);
 
-                    match hotkey {
-                        Some(Hotkey::Tall) => {
-                            let _ = ShowWindow(self.ruler.hwnd, SW_SHOW);
-                        }
-                        _ => {
-                            let _ = ShowWindow(self.ruler.hwnd, SW_HIDE);
-                        }
-                    }
-
-                    match hotkey {
-                        Some(Hotkey::Tall) => {
-                            let rect_width = 60;
-                            let rect_height = 500;
-
-                            StretchBlt(
-                                projector_hdc,
-                                0,
-                                0,
-                                self.width,
-                                self.height,
-                                Some(source_hdc),
-                                width / 2 - rect_width / 2,
-                                height / 2 - rect_height / 2,
-                                rect_width,
-                                rect_height,
-                                SRCCOPY,
-                            )
-                            .unwrap();
+                    let config = self.config.load();
+                    if let Some(settings) = config.find_projector(&self.name)
+                        && let Ok(client_size) = instance.get_client_size()
+                    {
+                        let regions = crop::regions(self.options.get(instance.hwnd), client_size);
+                        let projector_size = XY::new(self.width, self.height);
+
+                        // Post-processing needs the picture in memory.
+                        match (config.projector_backend, settings.isolation.enabled) {
+                            (Backend::Gdi, false) => render::render(
+                                &mut GdiRenderer {
+                                    source: source_hdc,
+                                    target: projector_hdc,
+                                },
+                                &settings.blits,
+                                regions,
+                                client_size,
+                                projector_size,
+                            ),
+                            _ => {
+                                let mut image = Image::new(projector_size);
+                                render::render(
+                                    &mut CapturingRenderer {
+                                        source: source_hdc,
+                                        target: &mut image,
+                                    },
+                                    &settings.blits,
+                                    regions,
+                                    client_size,
+                                    projector_size,
+                                );
+                                if settings.isolation.enabled {
+                                    isolate::isolate(&mut image, &settings.isolation);
+                                }
+                                image.present(projector_hdc, XY::new(0, 0));
+                            }
                         }
-                        Some(Hotkey::Thin) => {
-                            let e_height = self.width / 11;
-
-                            let pie_height = self.height - e_height;
-                            let pie_width = self.width;
 
-                            StretchBlt(
+                        if let Some(readout) = settings.readout
+                            && let Some(chart) = &**self.pie.load()
+                        {
+                            draw_readout(
                                 projector_hdc,
-                                0,
-                                0,
-                                self.width,
-                                e_height,
-                                Some(source_hdc),
-                                0,
-                                37,
-                                99,
-                                9,
-                                SRCCOPY,
-                            )
-                            .unwrap();
-
-                            StretchBlt(
-                                projector_hdc,
-                                0,
-                                e_height,
-                                pie_width,
-                                pie_height,
-                                Some(source_hdc),
-                                width - 340,
-                                height - 420,
-                                340,
-                                420,
-                                SRCCOPY,
-                            )
-                            .unwrap();
+                                chart,
+                                crop::destination_area(readout, projector_size),
+                            );
                         }
-                        _ => {}
                     }
 
                     ReleaseDC(Some(instance.hwnd), source_hdc);
                     EndPaint(hwnd, &raw const ps).unwrap();
 
-                    InvalidateRect(Some(hwnd), None, true).unwrap();
+                    if let Some(stats) = self.recorder.record(started, started.elapsed()) {
+                        self.events.publish(Event::ProjectorStats {
+                            name: self.name.clone(),
+                            stats,
+                        });
+                    }
 
                     LRESULT(0)
                 }
@@ -377,50 +397,500 @@ impl WndClass for ProjectorWindow {
     }
 }
 
-impl Projector {
-    pub fn hotkey_hook(&self, hotkey: Option<Hotkey>) {
+impl ProjectorWindow {
+    /// Moves the projector where its settings put it, and shows it.
+    fn place(&mut self, hwnd: HWND) {
+        let config = self.config.load();
+        let Some(settings) = config.find_projector(&self.name) else {
+            return;
+        };
+        let Some((position, size)) = self.placement(settings) else {
+            return;
+        };
+
+        self.width = size.x;
+        self.height = size.y;
+
         unsafe {
-            self.hotkey.store(Arc::new(hotkey));
-            let _ = ShowWindow(
-                self.hwnd,
-                if matches!(hotkey, Some(Hotkey::Tall | Hotkey::Thin)) {
-                    SW_SHOW
+            let insert_after = if settings.topmost {
+                HWND_TOPMOST
+            } else {
+                HWND_NOTOPMOST
+            };
+            if let Err(e) = SetWindowPos(
+                hwnd,
+                Some(insert_after),
+                position.x,
+                position.y,
+                size.x,
+                size.y,
+                SWP_FRAMECHANGED | SWP_NOACTIVATE,
+            ) {
+                log::warn!("Failed to place projector {}: {}", self.name, e);
+            }
+            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+
+            if settings.ruler {
+                self.ruler
+                    .set_window_pos(hwnd, position.x, position.y, size.x, size.y);
+                let _ = ShowWindow(self.ruler.hwnd, SW_SHOWNOACTIVATE);
+            } else {
+                let _ = ShowWindow(self.ruler.hwnd, SW_HIDE);
+            }
+        }
+    }
+
+    /// Works out the screen rect of the projector, next to the active
+    /// instance unless placed on a monitor of its own.
+    fn placement(&self, settings: &ProjectorSettings) -> Option<(XY, XY)> {
+        let instance = self.instances.active();
+        let game_monitor = instance.map(|instance| instance.get_monitor_info());
+
+        let monitor = settings
+            .monitor
+            .and_then(|i| monitors().get(i).copied())
+            .or(game_monitor)?;
+
+        let game = match instance {
+            Some(instance) if game_monitor == Some(monitor) => {
+                let rect = instance.get_window_rect().ok()?;
+                Some((
+                    XY::new(rect.left, rect.top),
+                    XY::new(rect.right - rect.left, rect.bottom - rect.top),
+                ))
+            }
+            _ => None,
+        };
+
+        Some(placement::place(settings.placement, monitor, game))
+    }
+}
+
+/// Looks for a thrown ender eye in `source` of `instance`, publishing where
+/// it is when that changes from `last`.
+fn read_eye(
+    instance: MinecraftInstance,
+    source: Source,
+    options: &OptionsCache,
+    events: &EventBus,
+    last: &mut Option<EyeReading>,
+) {
+    let Ok(size) = instance.get_client_size() else {
+        return;
+    };
+    let options = options.get(instance.hwnd);
+    let region = crop::source_region(source, crop::regions(options, size), size);
+
+    let image = unsafe {
+        let hdc = GetDC(Some(instance.hwnd));
+        let image = Image::capture(hdc, region);
+        ReleaseDC(Some(instance.hwnd), hdc);
+        image
+    };
+    let crosshair = size.x as f64 / 2.0 - region.position.x as f64;
+    let reading = image
+        .as_ref()
+        .and_then(|image| eye::detect(image, crosshair))
+        .map(|offset| EyeReading {
+            offset,
+            correction: eye::angle_correction(offset.pixels, size.y, options.fov as f64),
+        });
+
+    if *last != reading {
+        log::debug!("Read ender eye: {:?}", reading);
+        *last = reading;
+        events.publish(Event::EyeDetected(reading));
+    }
+}
+
+/// Writes out the path and wedges of `chart` over `area`, a line each.
+fn draw_readout(hdc: HDC, chart: &PieChart, area: Region) {
+    let lines: Vec<(String, Color)> = std::iter::once((chart.path.clone(), Color(255, 255, 255)))
+        .chain(chart.wedges.iter().map(|wedge| {
+            (
+                format!("{}  {}", wedge.label, PieChart::percent(wedge.percent)),
+                wedge.color,
+            )
+        }))
+        .collect();
+    let line_height = (area.size.y / lines.len() as i32).clamp(8, 96);
+
+    unsafe {
+        let font = CreateFontW(
+            line_height,
+            0,
+            0,
+            0,
+            FW_SEMIBOLD.0 as i32,
+            FALSE.0 as u32,
+            FALSE.0 as u32,
+            FALSE.0 as u32,
+            DEFAULT_CHARSET,
+            OUT_DEFAULT_PRECIS,
+            CLIP_DEFAULT_PRECIS,
+            DEFAULT_QUALITY,
+            FIXED_PITCH.0 as u32,
+            PCWSTR::default(),
+        );
+        let old = SelectObject(hdc, font.into());
+        SetBkMode(hdc, TRANSPARENT);
+
+        for (i, (line, color)) in lines.into_iter().enumerate() {
+            let mut text: Vec<u16> = line.encode_utf16().collect();
+            let top = area.position.y + i as i32 * line_height;
+
+            // A shadow keeps the text readable over the picture.
+            for (offset, color) in [(2, COLORREF(0)), (0, color.into())] {
+                SetTextColor(hdc, color);
+                DrawTextW(
+                    hdc,
+                    &mut text,
+                    &mut RECT {
+                        left: area.position.x + offset,
+                        top: top + offset,
+                        right: area.position.x + area.size.x + offset,
+                        bottom: top + line_height + offset,
+                    } as *mut RECT,
+                    DT_LEFT | DT_SINGLELINE | DT_NOCLIP,
+                );
+            }
+        }
+
+        SelectObject(hdc, old);
+        let _ = DeleteObject(font.into());
+    }
+}
+
+/// Position and size of every monitor, in the order Windows enumerates them.
+fn monitors() -> Vec<(XY, XY)> {
+    unsafe extern "system" fn callback(
+        _: HMONITOR,
+        _: HDC,
+        rect: *mut RECT,
+        lparam: LPARAM,
+    ) -> BOOL {
+        unsafe {
+            let monitors = &mut *(lparam.0 as *mut Vec<(XY, XY)>);
+            let RECT {
+                left,
+                top,
+                right,
+                bottom,
+            } = *rect;
+            monitors.push((XY::new(left, top), XY::new(right - left, bottom - top)));
+        }
+
+        TRUE
+    }
+
+    let mut monitors: Vec<(XY, XY)> = Vec::new();
+    unsafe {
+        let _ = EnumDisplayMonitors(
+            None,
+            None,
+            Some(callback),
+            LPARAM(&raw mut monitors as isize),
+        );
+    }
+
+    monitors
+}
+
+impl Projectors {
+    /// Shows the projectors of `mode` and hides the rest.
+    fn set_mode(&self, mode: Option<Hotkey>) {
+        self.mode.store(Arc::new(mode));
+
+        let config = self.config.load();
+        let mut shown = Vec::new();
+        for projector in self.windows.load().iter() {
+            let visible = config
+                .find_projector(&projector.name)
+                .is_some_and(|settings| settings.shown_in(mode));
+
+            unsafe {
+                if visible {
+                    let _ = PostMessageW(Some(projector.hwnd), WM_PLACE, WPARAM(0), LPARAM(0));
+                    shown.push(projector.clone());
                 } else {
-                    SW_HIDE
-                },
-            );
+                    let _ = ShowWindow(projector.hwnd, SW_HIDE);
+                }
+            }
+        }
+
+        self.shown.store(Arc::new(shown));
+        let _ = self.wake.send(());
+    }
+
+    /// Repaints the shown windows at the configured rate. Sleeps while none
+    /// are shown, or while there's nothing new to copy because the active
+    /// instance is minimized or there is none.
+    fn schedule(
+        &self,
+        instances: InstanceRegistry,
+        options: OptionsCache,
+        events: EventBus,
+        wake: mpsc::Receiver<()>,
+    ) {
+        let timer = match Timer::new() {
+            Ok(timer) => Some(timer),
+            Err(e) => {
+                log::warn!("Failed to create frame timer, falling back to sleep: {}", e);
+                None
+            }
+        };
+        let mut next = Instant::now();
+        let mut last_read = Instant::now();
+        let mut eye = None;
+
+        loop {
+            while wake.try_recv().is_ok() {}
+
+            let shown = self.shown.load_full();
+            if shown.is_empty() {
+                if wake.recv().is_err() {
+                    return;
+                }
+                next = Instant::now();
+                continue;
+            }
+
+            let instance = instances
+                .active()
+                .filter(|instance| !unsafe { IsIconic(instance.hwnd) }.as_bool());
+            let Some(instance) = instance else {
+                if let Err(mpsc::RecvTimeoutError::Disconnected) = wake.recv_timeout(RESTORE_CHECK)
+                {
+                    return;
+                }
+                next = Instant::now();
+                continue;
+            };
+
+            let config = self.config.load();
+            if last_read.elapsed() >= READ_INTERVAL {
+                last_read = Instant::now();
+
+                let settings: Vec<&ProjectorSettings> = shown
+                    .iter()
+                    .filter_map(|projector| config.find_projector(&projector.name))
+                    .collect();
+                if settings.iter().any(|settings| settings.readout.is_some()) {
+                    self.read_pie(instance, &options, &events);
+                }
+                if let Some(blit) = settings
+                    .iter()
+                    .filter(|settings| settings.detect_eye)
+                    .find_map(|settings| settings.blits.first())
+                {
+                    read_eye(instance, blit.source, &options, &events, &mut eye);
+                }
+            }
+
+            for projector in shown.iter() {
+                unsafe {
+                    let _ = InvalidateRect(Some(projector.hwnd), None, false);
+                }
+            }
+
+            let fps = config.projector_fps.max(1);
+            next = Instant::max(next + Duration::from_secs(1) / fps, Instant::now());
+            match &timer {
+                Some(timer) => timer.wait_until(next),
+                None => std::thread::sleep(next.saturating_duration_since(Instant::now())),
+            }
+        }
+    }
+
+    /// Reads the pie chart of `instance` for the readouts, publishing it
+    /// when it changes.
+    fn read_pie(&self, instance: MinecraftInstance, options: &OptionsCache, events: &EventBus) {
+        let Ok(size) = instance.get_client_size() else {
+            return;
+        };
+        let region = crop::regions(options.get(instance.hwnd), size).pie;
+
+        let image = unsafe {
+            let hdc = GetDC(Some(instance.hwnd));
+            let image = Image::capture(hdc, region);
+            ReleaseDC(Some(instance.hwnd), hdc);
+            image
+        };
+        let chart = image.as_ref().and_then(pie::analyze);
+
+        if **self.pie.load() != chart {
+            log::debug!("Read pie chart: {:?}", chart);
+            self.pie.store(Arc::new(chart.clone()));
+            events.publish(Event::PieChartRead(chart));
         }
     }
 
+    /// Creates windows for projectors added to the config and destroys those
+    /// of removed ones. Must run on the projector thread.
+    fn sync(&self, instances: &InstanceRegistry, options: &OptionsCache, events: &EventBus) {
+        let config = self.config.load();
+        let mut windows = Vec::new();
+
+        for projector in self.windows.load().iter() {
+            if config.find_projector(&projector.name).is_some() {
+                windows.push(projector.clone());
+            } else {
+                log::debug!("Removing projector {}", projector.name);
+                unsafe {
+                    let _ = DestroyWindow(projector.hwnd);
+                }
+            }
+        }
+
+        for settings in &config.projectors {
+            if windows
+                .iter()
+                .any(|projector| projector.name == settings.name)
+            {
+                continue;
+            }
+
+            log::debug!("Adding projector {}", settings.name);
+
+            let ruler = Ruler::spawn(self.config.clone());
+            let title = U16CString::from_str_truncate(&settings.name);
+            let hwnd = match wnd_class::spawn(
+                WS_EX_TOPMOST,
+                WS_POPUP,
+                PCWSTR(title.as_ptr()),
+                None,
+                None,
+                Box::new(ProjectorWindow {
+                    name: settings.name.clone(),
+                    config: self.config.clone(),
+                    instances: instances.clone(),
+                    options: options.clone(),
+                    events: events.clone(),
+                    pie: self.pie.clone(),
+                    recorder: FrameRecorder::default(),
+                    ruler,
+                    width: 0,
+                    height: 0,
+                }),
+            ) {
+                Ok(hwnd) => hwnd,
+                Err(e) => {
+                    log::warn!("Failed to create projector {}: {}", settings.name, e);
+                    continue;
+                }
+            };
+            ruler.set_owner(hwnd);
+
+            windows.push(Projector {
+                name: settings.name.clone(),
+                hwnd,
+            });
+        }
+
+        self.windows.store(Arc::new(windows));
+        self.set_mode(**self.mode.load());
+    }
+
     pub fn spawn(
-        instance: Arc<ArcSwapOption<MinecraftInstance>>,
+        instances: InstanceRegistry,
         config: Arc<ArcSwap<Config>>,
+        events: EventBus,
     ) -> Self {
-        let ruler = Ruler::spawn(config.clone());
+        let (tx, rx) = mpsc::channel();
+        let (wake, woken) = mpsc::channel();
+        let mode = Arc::new(ArcSwap::from_pointee(None));
+        let windows = Arc::new(ArcSwap::from_pointee(Vec::new()));
+        let shown = Arc::new(ArcSwap::from_pointee(Vec::new()));
+        let pie = Arc::new(ArcSwap::from_pointee(None));
+        let options = OptionsCache::spawn(config.clone(), instances.clone());
+
+        std::thread::spawn({
+            let config = config.clone();
+            let instances = instances.clone();
+            let options = options.clone();
+            let events = events.clone();
+            let mode = mode.clone();
+            let windows = windows.clone();
+            let shown = shown.clone();
+            let wake = wake.clone();
+            let pie = pie.clone();
+            move || {
+                let projectors = Projectors {
+                    thread_id: unsafe { GetCurrentThreadId() },
+                    mode,
+                    windows,
+                    shown,
+                    wake,
+                    pie,
+                    config,
+                };
+
+                projectors.sync(&instances, &options, &events);
+                let _ = tx.send(projectors.thread_id);
+
+                let mut msg = MSG::default();
+                unsafe {
+                    loop {
+                        match GetMessageW(&raw mut msg, None, 0, 0) {
+                            BOOL(-1) => {}
+                            BOOL(0) => {
+                                break;
+                            }
+                            BOOL(_) if msg.hwnd.is_invalid() && msg.message == WM_SYNC => {
+                                projectors.sync(&instances, &options, &events);
+                            }
+                            BOOL(_) => {
+                                let _ = TranslateMessage(&raw const msg);
+                                DispatchMessageW(&raw const msg);
+                            }
+                        }
+                    }
+                }
+            }
+        });
 
-        let hotkey = Arc::new(ArcSwap::from_pointee(None));
+        let projectors = Self {
+            thread_id: rx.recv().unwrap(),
+            mode,
+            windows,
+            shown,
+            wake,
+            pie,
+            config,
+        };
 
-        let projector_wnd = wnd_class::spawn(
-            WS_EX_TOPMOST,
-            WS_POPUP,
-            PCWSTR(widestring::u16cstr!("Projector").as_ptr()),
-            None,
-            None,
-            Box::new(ProjectorWindow {
-                instance,
-                width: 0,
-                height: 0,
-                ruler,
-                hotkey: hotkey.clone(),
-            }),
-        )
-        .unwrap();
+        std::thread::spawn({
+            let projectors = projectors.clone();
+            let events = events.clone();
+            move || projectors.schedule(instances, options, events, woken)
+        });
 
-        ruler.set_owner(projector_wnd);
+        std::thread::spawn({
+            let projectors = projectors.clone();
+            let mut events = events.subscribe();
+            move || {
+                futures::executor::block_on(async {
+                    while let Some(ev) = events.next().await {
+                        match ev {
+                            Event::ConfigReloaded => unsafe {
+                                let _ = PostThreadMessageW(
+                                    projectors.thread_id,
+                                    WM_SYNC,
+                                    WPARAM(0),
+                                    LPARAM(0),
+                                );
+                            },
+                            Event::ModeChanged { to, .. } => projectors.set_mode(to),
+                            Event::InstanceAttached { .. } => {
+                                let _ = projectors.wake.send(());
+                            }
+                            _ => {}
+                        }
+                    }
+                })
+            }
+        });
 
-        Self {
-            hwnd: projector_wnd,
-            hotkey,
-        }
+        projectors
     }
 }
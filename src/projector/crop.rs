@@ -0,0 +1,273 @@
+use crate::{
+    config::{
+        projector::{Blit, Destination, Scaling, Source},
+        xy::XY,
+    },
+    options::GameOptions,
+    projector::pie,
+};
+
+/// A rectangle of the game window's client area.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub position: XY,
+    pub size: XY,
+}
+
+/// Where thin mode's projections are found in the game window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CropRegions {
+    /// The F3 profiler pie chart with its legend.
+    pub pie: Region,
+    /// The "E:" line of the F3 text, counting rendered entities.
+    pub entities: Region,
+}
+
+/// Lines of F3 text above the entity counter.
+const ENTITIES_LINE: i32 = 4;
+/// Height of a line of F3 text in GUI pixels, background included.
+const LINE_HEIGHT: i32 = 9;
+/// Width of the entity counter in GUI pixels, up to its block entities.
+const ENTITIES_WIDTH: i32 = 99;
+
+/// Computes the crop regions for a client area of `size`, with the GUI
+/// scale the game would pick for it.
+pub fn regions(options: GameOptions, size: XY) -> CropRegions {
+    let scale = options.effective_gui_scale(size);
+
+    // The text starts 2 GUI pixels down, its background 1 pixel above that.
+    let entities = Region {
+        position: XY::new(0, (1 + LINE_HEIGHT * ENTITIES_LINE) * scale),
+        size: XY::new(ENTITIES_WIDTH * scale, LINE_HEIGHT * scale),
+    };
+
+    let pie_size = XY::new(pie::SIZE.x.min(size.x), pie::SIZE.y.min(size.y));
+    let pie = Region {
+        position: XY::new(size.x - pie_size.x, size.y - pie_size.y),
+        size: pie_size,
+    };
+
+    CropRegions { pie, entities }
+}
+
+/// Finds the rect of the game window a blit copies from.
+pub fn source_region(source: Source, regions: CropRegions, size: XY) -> Region {
+    match source {
+        Source::Rect {
+            anchor,
+            offset,
+            size: rect_size,
+        } => Region {
+            position: anchor.point(size) + offset - anchor.point(rect_size),
+            size: rect_size,
+        },
+        Source::Pie => regions.pie,
+        Source::Entities => regions.entities,
+    }
+}
+
+/// Finds the rect of a projector of `size` covered by `destination`.
+pub fn destination_area(destination: Destination, size: XY) -> Region {
+    Region {
+        position: XY::new(
+            (destination.x * size.x as f32).round() as i32,
+            (destination.y * size.y as f32).round() as i32,
+        ),
+        size: XY::new(
+            (destination.width * size.x as f32).round() as i32,
+            (destination.height * size.y as f32).round() as i32,
+        ),
+    }
+}
+
+/// Finds the rect of a projector of `size` that a blit of `source` size
+/// draws to, within its [`destination_area`].
+pub fn destination_region(blit: &Blit, source: XY, size: XY) -> Region {
+    let area = destination_area(blit.destination, size);
+    if source.x <= 0 || source.y <= 0 {
+        return area;
+    }
+
+    match blit.scaling {
+        Scaling::Stretch => area,
+        Scaling::Fit => {
+            let scale = f32::min(
+                area.size.x as f32 / source.x as f32,
+                area.size.y as f32 / source.y as f32,
+            );
+
+            Region {
+                position: area.position,
+                size: XY::new(
+                    (source.x as f32 * scale).round() as i32,
+                    (source.y as f32 * scale).round() as i32,
+                ),
+            }
+        }
+        Scaling::Integer => {
+            let scale = i32::min(area.size.x / source.x, area.size.y / source.y).max(1);
+            let size = XY::new(source.x * scale, source.y * scale);
+
+            Region {
+                position: area.position + (area.size - size) / 2,
+                size,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::projector::Filter;
+
+    fn blit(destination: Destination, scaling: Scaling) -> Blit {
+        Blit {
+            source: Source::Pie,
+            destination,
+            scaling,
+            filter: Filter::Nearest,
+        }
+    }
+
+    fn region(x: i32, y: i32, width: i32, height: i32) -> Region {
+        Region {
+            position: XY::new(x, y),
+            size: XY::new(width, height),
+        }
+    }
+
+    const RIGHT_HALF: Destination = Destination {
+        x: 0.5,
+        y: 0.0,
+        width: 0.5,
+        height: 1.0,
+    };
+
+    fn options(gui_scale: i32) -> GameOptions {
+        GameOptions {
+            gui_scale,
+            ..GameOptions::default()
+        }
+    }
+
+    #[test]
+    fn entities_line_at_scale_one_in_thin_mode() {
+        let regions = regions(options(1), XY::new(400, 1800));
+
+        assert_eq!(regions.entities, region(0, 37, 99, 9));
+        assert_eq!(regions.pie, region(60, 1380, 340, 420));
+    }
+
+    #[test]
+    fn entities_line_grows_with_the_gui_scale() {
+        let size = XY::new(1920, 1080);
+
+        for scale in 1..=4 {
+            assert_eq!(
+                regions(options(scale), size).entities,
+                region(0, 37 * scale, 99 * scale, 9 * scale)
+            );
+        }
+        // Auto picks 4 for 1080p.
+        assert_eq!(regions(options(0), size).entities, region(0, 148, 396, 36));
+    }
+
+    #[test]
+    fn entities_line_follows_unicode_rounding() {
+        let options = GameOptions {
+            gui_scale: 3,
+            force_unicode_font: true,
+            ..GameOptions::default()
+        };
+
+        assert_eq!(
+            regions(options, XY::new(1920, 1080)).entities,
+            region(0, 148, 396, 36)
+        );
+    }
+
+    #[test]
+    fn pie_region_is_the_bottom_right_corner() {
+        assert_eq!(
+            regions(options(0), XY::new(1920, 1080)).pie,
+            region(1580, 660, 340, 420)
+        );
+        // Clamped to windows smaller than the chart.
+        assert_eq!(
+            regions(options(0), XY::new(300, 300)).pie,
+            region(0, 0, 300, 300)
+        );
+    }
+
+    #[test]
+    fn destination_area_is_a_fraction_of_the_projector() {
+        let size = XY::new(800, 600);
+
+        assert_eq!(
+            destination_area(Destination::FULL, size),
+            region(0, 0, 800, 600)
+        );
+        assert_eq!(destination_area(RIGHT_HALF, size), region(400, 0, 400, 600));
+    }
+
+    #[test]
+    fn stretch_fills_the_area() {
+        let blit = blit(RIGHT_HALF, Scaling::Stretch);
+
+        assert_eq!(
+            destination_region(&blit, XY::new(60, 500), XY::new(800, 600)),
+            region(400, 0, 400, 600)
+        );
+    }
+
+    #[test]
+    fn fit_keeps_the_aspect_ratio_in_the_top_left() {
+        let blit = blit(Destination::FULL, Scaling::Fit);
+
+        assert_eq!(
+            destination_region(&blit, XY::new(340, 420), XY::new(800, 600)),
+            region(0, 0, 486, 600)
+        );
+        assert_eq!(
+            destination_region(&blit, XY::new(400, 100), XY::new(800, 600)),
+            region(0, 0, 800, 200)
+        );
+    }
+
+    #[test]
+    fn integer_centers_a_whole_multiple() {
+        let blit = blit(Destination::FULL, Scaling::Integer);
+
+        // Letterboxed above and below.
+        assert_eq!(
+            destination_region(&blit, XY::new(99, 9), XY::new(400, 300)),
+            region(2, 132, 396, 36)
+        );
+        // Pillarboxed left and right.
+        assert_eq!(
+            destination_region(&blit, XY::new(60, 500), XY::new(800, 600)),
+            region(370, 50, 60, 500)
+        );
+    }
+
+    #[test]
+    fn integer_never_scales_below_one() {
+        let blit = blit(Destination::FULL, Scaling::Integer);
+
+        assert_eq!(
+            destination_region(&blit, XY::new(340, 420), XY::new(200, 200)),
+            region(-70, -110, 340, 420)
+        );
+    }
+
+    #[test]
+    fn empty_sources_take_the_whole_area() {
+        for scaling in Scaling::ALL {
+            assert_eq!(
+                destination_region(&blit(RIGHT_HALF, scaling), XY::new(0, 0), XY::new(800, 600)),
+                region(400, 0, 400, 600)
+            );
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::projector::render::Image;
+
+/// Where a thrown ender eye is relative to the crosshair.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EyeOffset {
+    /// Horizontal distance from the crosshair to the center of the eye in
+    /// game pixels, positive to the right.
+    pub pixels: f64,
+    /// From 0 to 1, how sure the eye was told apart from the background.
+    pub confidence: f64,
+}
+
+/// An [`EyeOffset`] and the angle it stands for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EyeReading {
+    pub offset: EyeOffset,
+    /// Degrees to add to the angle shown in F3 to point at the eye.
+    pub correction: f64,
+}
+
+/// The game's field of view unless set otherwise, in degrees.
+pub const DEFAULT_FOV: f64 = 70.0;
+
+/// Pixels the eye's sprite should cover at least to be trusted fully.
+const MIN_PIXELS: usize = 4;
+
+/// How strongly a pixel looks like the teal of an ender eye, 0 if not at
+/// all. Grass and leaves are greener than they are blue and the sky is
+/// bluer than it is green, so neither counts.
+fn eye_weight(pixel: u32) -> u32 {
+    let r = (pixel >> 16) & 0xFF;
+    let g = (pixel >> 8) & 0xFF;
+    let b = pixel & 0xFF;
+
+    if g > r + 24 && b > r + 16 && b < g + 32 {
+        g - r
+    } else {
+        0
+    }
+}
+
+/// Finds the ender eye in a capture of the tall mode strip. `crosshair` is
+/// the horizontal position of the crosshair in the image, between pixels for
+/// an even window width. The eye is the strongest connected patch of its
+/// color, and its center the patch's weighted centroid.
+pub fn detect(image: &Image, crosshair: f64) -> Option<EyeOffset> {
+    let weights: Vec<u32> = image
+        .pixels
+        .iter()
+        .map(|&pixel| eye_weight(pixel))
+        .collect();
+    let total: u64 = weights.iter().map(|&weight| weight as u64).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let width = image.size.x as usize;
+    let mut seen = vec![false; weights.len()];
+    let mut best: Option<(u64, f64, usize)> = None;
+
+    for start in 0..weights.len() {
+        if seen[start] || weights[start] == 0 {
+            continue;
+        }
+
+        let mut stack = vec![start];
+        seen[start] = true;
+        let (mut weight, mut moment, mut count) = (0u64, 0.0, 0);
+
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % width, i / width);
+            weight += weights[i] as u64;
+            moment += weights[i] as f64 * (x as f64 + 0.5);
+            count += 1;
+
+            let neighbours = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < width).then_some(i + 1),
+                (y > 0).then(|| i - width),
+                (i + width < weights.len()).then_some(i + width),
+            ];
+            for j in neighbours.into_iter().flatten() {
+                if !seen[j] && weights[j] != 0 {
+                    seen[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+
+        if best.is_none_or(|(best_weight, ..)| weight > best_weight) {
+            best = Some((weight, moment / weight as f64, count));
+        }
+    }
+
+    let (weight, center, count) = best?;
+    let share = weight as f64 / total as f64;
+    let size = (count as f64 / MIN_PIXELS as f64).min(1.0);
+
+    Some(EyeOffset {
+        pixels: center - crosshair,
+        confidence: share * size,
+    })
+}
+
+/// The angle in degrees between the crosshair and a point `pixels` to its
+/// right, in a window `height` pixels tall with a vertical field of view of
+/// `fov` degrees.
+pub fn angle_correction(pixels: f64, height: i32, fov: f64) -> f64 {
+    let focal_length = height as f64 / 2.0 / (fov.to_radians() / 2.0).tan();
+
+    (pixels / focal_length).atan().to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn fixture(name: &str) -> Image {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/eye")
+            .join(format!("{}.bmp", name));
+        Image::read_bmp(&path).unwrap()
+    }
+
+    /// Stored 60 pixel wide tall mode crops, with the crosshair between
+    /// their 30th and 31st columns, the offset of the eye's center and how
+    /// confident detection should be. The `sub_pixel` crops are smoothly
+    /// downscaled, so the eye's edges are blended with the background.
+    const CORPUS: [(&str, f64, f64); 10] = [
+        ("sky_right", 8.0, 1.0),
+        ("sky_left", -13.0, 1.0),
+        ("centered", 0.0, 1.0),
+        ("over_grass", -5.0, 1.0),
+        ("night", 13.0, 1.0),
+        ("stray_pixels", 4.0, 0.9),
+        ("sub_pixel_right", 11.1, 1.0),
+        ("sub_pixel_left", -10.45, 1.0),
+        ("sub_pixel_centered", 0.35, 1.0),
+        ("sub_pixel_grass", -5.45, 1.0),
+    ];
+
+    #[test]
+    fn finds_the_eye_in_stored_crops() {
+        for (name, pixels, confidence) in CORPUS {
+            let image = fixture(name);
+            let offset = detect(&image, image.size.x as f64 / 2.0)
+                .unwrap_or_else(|| panic!("no eye found in {}", name));
+
+            assert!(
+                (offset.pixels - pixels).abs() < 0.2,
+                "{}: offset {} instead of {}",
+                name,
+                offset.pixels,
+                pixels
+            );
+            assert!(
+                (offset.confidence - confidence).abs() < 0.05,
+                "{}: confidence {} instead of {}",
+                name,
+                offset.confidence,
+                confidence
+            );
+        }
+    }
+
+    #[test]
+    fn finds_nothing_without_an_eye() {
+        let image = fixture("no_eye");
+        assert_eq!(detect(&image, 30.0), None);
+    }
+
+    #[test]
+    fn small_patches_are_less_certain() {
+        let mut image = Image::new(crate::config::xy::XY::new(60, 20));
+        image.pixels[10 * 60 + 40] = 0x2D8F73;
+
+        let offset = detect(&image, 30.0).unwrap();
+        assert_eq!(offset.pixels, 10.5);
+        assert_eq!(offset.confidence, 0.25);
+    }
+
+    #[test]
+    fn corrects_by_the_angle_of_the_offset() {
+        assert_eq!(angle_correction(0.0, 16384, DEFAULT_FOV), 0.0);
+
+        let one = angle_correction(1.0, 16384, DEFAULT_FOV);
+        assert!((one - 0.004897).abs() < 1e-6, "{}", one);
+        assert_eq!(angle_correction(-1.0, 16384, DEFAULT_FOV), -one);
+
+        // A focal length of one pixel puts a pixel's offset at 45 degrees.
+        let height = (2.0 * (DEFAULT_FOV.to_radians() / 2.0).tan()).round() as i32;
+        let fov = 2.0 * (height as f64 / 2.0).atan().to_degrees();
+        assert!((angle_correction(1.0, height, fov) - 45.0).abs() < 1e-9);
+    }
+}
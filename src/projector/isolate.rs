@@ -0,0 +1,168 @@
+use crate::{config::projector::Isolation, projector::render::Image};
+
+fn channels(pixel: u32) -> [i32; 3] {
+    [
+        ((pixel >> 16) & 0xFF) as i32,
+        ((pixel >> 8) & 0xFF) as i32,
+        (pixel & 0xFF) as i32,
+    ]
+}
+
+fn pixel([r, g, b]: [i32; 3]) -> u32 {
+    let clamp = |c: i32| c.clamp(0, 255) as u32;
+
+    clamp(r) << 16 | clamp(g) << 8 | clamp(b)
+}
+
+/// Keeps the pixels matching one of the isolated colors, with their contrast
+/// adjusted, and dims and possibly desaturates the rest.
+pub fn isolate(image: &mut Image, isolation: &Isolation) {
+    let tolerance = isolation.tolerance as i32;
+    let dim = isolation.dim.min(100) as i32;
+    let contrast = isolation.contrast as i32;
+
+    let colors: Vec<[i32; 3]> = isolation
+        .colors
+        .iter()
+        .map(|color| [color.0 as i32, color.1 as i32, color.2 as i32])
+        .collect();
+
+    for p in &mut image.pixels {
+        let c = channels(*p);
+
+        let kept = colors
+            .iter()
+            .any(|color| c.iter().zip(color).all(|(a, b)| (a - b).abs() <= tolerance));
+
+        *p = if kept {
+            pixel(c.map(|c| 128 + (c - 128) * contrast / 100))
+        } else {
+            let c = if isolation.desaturate {
+                // Rec. 601 luma, in integer weights out of 1000.
+                let luma = (c[0] * 299 + c[1] * 587 + c[2] * 114) / 1000;
+                [luma; 3]
+            } else {
+                c
+            };
+
+            pixel(c.map(|c| c * dim / 100))
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{color::Color, xy::XY};
+
+    /// Runs `isolation` over a row of `pixels`.
+    fn isolated(pixels: &[u32], isolation: Isolation) -> Vec<u32> {
+        let mut image = Image {
+            size: XY::new(pixels.len() as i32, 1),
+            pixels: pixels.to_vec(),
+        };
+        isolate(&mut image, &isolation);
+        image.pixels
+    }
+
+    fn keeping(color: Color) -> Isolation {
+        Isolation {
+            enabled: true,
+            colors: vec![color],
+            tolerance: 8,
+            dim: 100,
+            desaturate: false,
+            contrast: 100,
+        }
+    }
+
+    const BLUE: Color = Color(100, 150, 200);
+
+    #[test]
+    fn keeps_colors_within_the_tolerance() {
+        let isolation = Isolation {
+            dim: 0,
+            ..keeping(BLUE)
+        };
+
+        assert_eq!(
+            isolated(&[0x6496C8, 0x6C8EC0, 0x6D96C8, 0x6496D1], isolation),
+            [0x6496C8, 0x6C8EC0, 0x000000, 0x000000]
+        );
+    }
+
+    #[test]
+    fn any_isolated_color_is_kept() {
+        let isolation = Isolation {
+            colors: vec![BLUE, Color(255, 255, 255)],
+            dim: 0,
+            ..keeping(BLUE)
+        };
+
+        assert_eq!(
+            isolated(&[0xFFFFFF, 0x6496C8, 0xFF0000], isolation),
+            [0xFFFFFF, 0x6496C8, 0x000000]
+        );
+    }
+
+    #[test]
+    fn dims_the_rest() {
+        let pixels = [0xC86428];
+
+        for (dim, expected) in [
+            (50, 0x643214),
+            (0, 0x000000),
+            (100, 0xC86428),
+            (250, 0xC86428),
+        ] {
+            let isolation = Isolation {
+                dim,
+                ..keeping(BLUE)
+            };
+            assert_eq!(isolated(&pixels, isolation), [expected], "dim {}", dim);
+        }
+    }
+
+    #[test]
+    fn desaturates_the_rest() {
+        let isolation = Isolation {
+            desaturate: true,
+            ..keeping(BLUE)
+        };
+        assert_eq!(
+            isolated(&[0xC86428, 0x6496C8], isolation.clone()),
+            [0x7B7B7B, 0x6496C8]
+        );
+
+        let isolation = Isolation {
+            dim: 50,
+            ..isolation
+        };
+        assert_eq!(isolated(&[0xC86428], isolation), [0x3D3D3D]);
+    }
+
+    #[test]
+    fn adjusts_the_contrast_of_kept_pixels() {
+        let contrast = |contrast| Isolation {
+            contrast,
+            ..keeping(BLUE)
+        };
+
+        assert_eq!(isolated(&[0x6496C8], contrast(200)), [0x48ACFF]);
+        assert_eq!(isolated(&[0x6496C8], contrast(50)), [0x728BA4]);
+        assert_eq!(isolated(&[0x6496C8], contrast(0)), [0x808080]);
+        // Other pixels are left alone.
+        assert_eq!(isolated(&[0x204060], contrast(200)), [0x204060]);
+    }
+
+    #[test]
+    fn without_colors_everything_is_dimmed() {
+        let isolation = Isolation {
+            colors: Vec::new(),
+            dim: 50,
+            ..keeping(BLUE)
+        };
+
+        assert_eq!(isolated(&[0x6496C8], isolation), [0x324B64]);
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::{Duration, Instant};
+
+use windows::{
+    Win32::{
+        Foundation::{CloseHandle, HANDLE},
+        System::Threading::{
+            CREATE_WAITABLE_TIMER_HIGH_RESOLUTION, CreateWaitableTimerExW, INFINITE,
+            SetWaitableTimer, TIMER_ALL_ACCESS, WaitForSingleObject,
+        },
+    },
+    core::PCWSTR,
+};
+
+/// A high resolution waitable timer, for sleeping until the next frame more
+/// precisely than `thread::sleep` does.
+pub struct Timer {
+    handle: HANDLE,
+}
+
+unsafe impl Send for Timer {}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
+
+impl Timer {
+    pub fn new() -> windows::core::Result<Self> {
+        let handle = unsafe {
+            CreateWaitableTimerExW(
+                None,
+                PCWSTR::null(),
+                CREATE_WAITABLE_TIMER_HIGH_RESOLUTION,
+                TIMER_ALL_ACCESS.0,
+            )?
+        };
+
+        Ok(Self { handle })
+    }
+
+    /// Blocks until `deadline`, returning right away if it has passed.
+    pub fn wait_until(&self, deadline: Instant) {
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+            return;
+        };
+
+        // Negative due times are relative, in units of 100ns.
+        let due = -((remaining.as_nanos() / 100) as i64).max(1);
+        unsafe {
+            if SetWaitableTimer(self.handle, &raw const due, 0, None, None, false).is_err() {
+                std::thread::sleep(remaining);
+                return;
+            }
+            WaitForSingleObject(self.handle, INFINITE);
+        }
+    }
+}
+
+/// How a projector kept up over the last second.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Frames painted.
+    pub frames: u32,
+    /// Mean time from the start of a frame to the start of the next.
+    pub interval: Duration,
+    /// Longest time from the start of a frame to the start of the next.
+    pub worst_interval: Duration,
+    /// Mean time spent painting a frame.
+    pub paint: Duration,
+}
+
+impl FrameStats {
+    /// Frames per second going by the mean interval.
+    pub fn fps(&self) -> f64 {
+        1.0 / self.interval.as_secs_f64()
+    }
+}
+
+/// Collects frame timings into [`FrameStats`] once a second.
+#[derive(Debug, Default)]
+pub struct FrameRecorder {
+    /// Start of the last frame, kept from one second to the next.
+    last: Option<Instant>,
+    /// Start of the first frame of this second.
+    since: Option<Instant>,
+    frames: u32,
+    intervals: u32,
+    total_interval: Duration,
+    worst_interval: Duration,
+    total_paint: Duration,
+}
+
+impl FrameRecorder {
+    /// Records a frame that started at `started` and took `paint` to draw,
+    /// giving the stats of the past second once one has gone by.
+    pub fn record(&mut self, started: Instant, paint: Duration) -> Option<FrameStats> {
+        let since = *self.since.get_or_insert(started);
+        if let Some(last) = self.last.replace(started) {
+            let interval = started.saturating_duration_since(last);
+            self.intervals += 1;
+            self.total_interval += interval;
+            self.worst_interval = self.worst_interval.max(interval);
+        }
+        self.frames += 1;
+        self.total_paint += paint;
+
+        if started.saturating_duration_since(since) < Duration::from_secs(1) {
+            return None;
+        }
+
+        let stats = FrameStats {
+            frames: self.frames,
+            interval: self.total_interval / self.intervals.max(1),
+            worst_interval: self.worst_interval,
+            paint: self.total_paint / self.frames,
+        };
+        *self = Self {
+            last: self.last,
+            ..Self::default()
+        };
+
+        Some(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn measures_time_between_frames() {
+        let start = Instant::now();
+        let mut recorder = FrameRecorder::default();
+
+        // Quick paints, but every 20 ms with one frame 60 ms late.
+        let mut at = start;
+        assert_eq!(recorder.record(at, ms(2)), None);
+        for i in 1..48 {
+            at += if i == 24 { ms(60) } else { ms(20) };
+            assert_eq!(recorder.record(at, ms(2)), None);
+        }
+        let stats = recorder.record(at + ms(20), ms(2)).unwrap();
+
+        assert_eq!(stats.frames, 49);
+        assert_eq!(stats.interval, ms(1000) / 48);
+        assert_eq!(stats.worst_interval, ms(60));
+        assert_eq!(stats.paint, ms(2));
+    }
+
+    #[test]
+    fn carries_the_last_frame_into_the_next_second() {
+        let start = Instant::now();
+        let mut recorder = FrameRecorder::default();
+
+        recorder.record(start, ms(1));
+        let stats = recorder.record(start + ms(1000), ms(1)).unwrap();
+        assert_eq!((stats.frames, stats.interval), (2, ms(1000)));
+
+        // The next second starts with the gap since the frame before.
+        assert_eq!(recorder.record(start + ms(1500), ms(1)), None);
+        let stats = recorder.record(start + ms(2500), ms(1)).unwrap();
+        assert_eq!(stats.interval, ms(750));
+        assert_eq!(stats.worst_interval, ms(1000));
+    }
+
+    #[test]
+    fn reports_frames_per_second() {
+        let stats = FrameStats {
+            frames: 61,
+            interval: ms(16) + Duration::from_micros(667),
+            ..FrameStats::default()
+        };
+        assert!((stats.fps() - 60.0).abs() < 0.01);
+    }
+}
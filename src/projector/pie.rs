@@ -0,0 +1,361 @@
+pub mod font;
+
+use crate::{
+    config::{color::Color, xy::XY},
+    projector::render::Image,
+};
+
+/// One entry of the pie chart's legend.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Wedge {
+    /// The profiler section, `unspecified` for the rest of its parent.
+    pub label: String,
+    pub color: Color,
+    /// Share of the parent section, in hundredths of a percent.
+    pub percent: u32,
+    /// Share of the whole frame, in hundredths of a percent.
+    pub global: u32,
+}
+
+/// What the F3 profiler pie chart currently shows.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PieChart {
+    /// The section drilled down into, such as `root.gameRenderer.level`.
+    pub path: String,
+    pub wedges: Vec<Wedge>,
+}
+
+impl PieChart {
+    /// Formats a share in hundredths of a percent like the game does.
+    pub fn percent(hundredths: u32) -> String {
+        format!("{}.{:02}%", hundredths / 100, hundredths % 100)
+    }
+}
+
+/// Half the width of the pie, where its text lines start and end.
+const RADIUS: i32 = 160;
+/// Space the game leaves between the chart's text and the window's right
+/// edge.
+const MARGIN: i32 = 10;
+/// Center of the pie from the bottom right corner of the window. The chart
+/// is drawn in window pixels whatever the GUI scale.
+const CENTER_FROM_CORNER: XY = XY {
+    x: RADIUS + MARGIN,
+    y: 320,
+};
+/// Top of the header line from the center.
+const HEADER: i32 = -96;
+/// The bottom right corner of the window holding the chart, from a margin
+/// left of its text to a few pixels above its header.
+pub const SIZE: XY = XY {
+    x: 2 * (RADIUS + MARGIN),
+    y: CENTER_FROM_CORNER.y - HEADER + 4,
+};
+/// Top of the first legend line from the center.
+const LEGEND: i32 = 100;
+const LINE_HEIGHT: i32 = 8;
+/// How far a channel of a text pixel may be from the line's color, for the
+/// chart's slightly see-through background.
+const TOLERANCE: u32 = 24;
+/// Text is drawn with a shadow a quarter as bright, so anything darker than
+/// this can't be text.
+const MIN_BRIGHTNESS: u32 = 0x40;
+/// Blank columns between words. Letters are a column apart, and a space
+/// adds four more.
+const SPACE: usize = 3;
+
+fn channels(pixel: u32) -> [u32; 3] {
+    [(pixel >> 16) & 0xFF, (pixel >> 8) & 0xFF, pixel & 0xFF]
+}
+
+fn close(a: u32, b: u32) -> bool {
+    channels(a)
+        .into_iter()
+        .zip(channels(b))
+        .all(|(a, b)| a.abs_diff(b) <= TOLERANCE)
+}
+
+fn to_color(pixel: u32) -> Color {
+    let [r, g, b] = channels(pixel);
+    Color(r as u8, g as u8, b as u8)
+}
+
+/// The color of the line of text at `(x, y)`, found from the full height
+/// left edge of the `[` it starts with.
+fn line_color(image: &Image, x: i32, y: i32) -> Option<u32> {
+    if x < 0 || y < 0 || x >= image.size.x || y + 7 > image.size.y {
+        return None;
+    }
+
+    let color = image.get(x, y);
+    let bright = channels(color).into_iter().max()? >= MIN_BRIGHTNESS;
+
+    (bright && (1..7).all(|dy| close(image.get(x, y + dy), color))).then_some(color)
+}
+
+/// Reads the line of `color` text whose top left is at `(x, y)`, up to
+/// `right`. Words are split by single spaces, unknown glyphs replaced.
+fn read_line(image: &Image, x: i32, y: i32, right: i32, color: u32) -> String {
+    let column = |x: i32| -> u8 {
+        (0..8)
+            .filter(|&dy| y + dy < image.size.y && close(image.get(x, y + dy), color))
+            .fold(0, |bits, dy| bits | 1 << dy)
+    };
+
+    let mut text = String::new();
+    let mut glyph: Vec<u8> = Vec::new();
+    let mut blank = 0;
+
+    for x in x..=right.min(image.size.x) {
+        let bits = if x < image.size.x { column(x) } else { 0 };
+        if bits != 0 {
+            if blank >= SPACE && !text.is_empty() {
+                text.push(' ');
+            }
+            blank = 0;
+            glyph.push(bits);
+            continue;
+        }
+
+        blank += 1;
+        if glyph.is_empty() {
+            continue;
+        }
+
+        let mut rows = [0u8; 8];
+        for (dx, bits) in glyph.iter().enumerate() {
+            for (dy, row) in rows.iter_mut().enumerate() {
+                if bits & 1 << dy != 0 {
+                    *row |= 1 << dx;
+                }
+            }
+        }
+        text.push(font::find(glyph.len(), rows).unwrap_or(char::REPLACEMENT_CHARACTER));
+        glyph.clear();
+    }
+
+    text
+}
+
+/// Parses a share such as `12.34%` into hundredths of a percent.
+fn parse_percent(text: &str) -> Option<u32> {
+    let (whole, fraction) = text.strip_suffix('%')?.split_once('.')?;
+    if fraction.len() != 2 {
+        return None;
+    }
+
+    Some(whole.parse::<u32>().ok()? * 100 + fraction.parse::<u32>().ok()?)
+}
+
+/// Reads the pie chart from a capture of the bottom right corner of the game
+/// window, such as the projectors' pie region or a whole screenshot. None if
+/// no chart is shown there.
+pub fn analyze(image: &Image) -> Option<PieChart> {
+    let center_x = image.size.x - CENTER_FROM_CORNER.x;
+    let center_y = image.size.y - CENTER_FROM_CORNER.y;
+    let left = center_x - RADIUS;
+    let right = center_x + RADIUS;
+
+    let mut wedges = Vec::new();
+    for line in 0.. {
+        let y = center_y + LEGEND + line * LINE_HEIGHT;
+        let Some(color) = line_color(image, left, y) else {
+            break;
+        };
+
+        let text = read_line(image, left, y, right, color);
+        let words: Vec<&str> = text.split(' ').collect();
+        let [_, label @ .., percent, global] = words.as_slice() else {
+            break;
+        };
+        // A misread share only loses its own line.
+        let (Some(percent), Some(global)) = (parse_percent(percent), parse_percent(global)) else {
+            continue;
+        };
+
+        wedges.push(Wedge {
+            label: label.join(" "),
+            color: to_color(color),
+            percent,
+            global,
+        });
+    }
+
+    if wedges.is_empty() {
+        return None;
+    }
+
+    // The header has no `[0]` while in the unspecified part of a section.
+    let y = center_y + HEADER;
+    let path = line_color(image, left, y)
+        .map(|color| read_line(image, left, y, right, color))
+        .and_then(|text| {
+            let words: Vec<&str> = text.split(' ').collect();
+            match words.as_slice() {
+                [_, path @ .., _] => Some(path.join(" ")),
+                _ => None,
+            }
+        })
+        .unwrap_or_default();
+
+    Some(PieChart { path, wedges })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn fixture(name: &str) -> Image {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/pie")
+            .join(format!("{}.bmp", name));
+        Image::read_bmp(&path).unwrap()
+    }
+
+    /// Draws `text` the way the game does, with its top left at `(x, y)`.
+    fn draw(image: &mut Image, x: i32, y: i32, text: &str, color: u32) {
+        let mut x = x;
+        for char in text.chars() {
+            if char == ' ' {
+                x += 4;
+                continue;
+            }
+
+            let glyph = font::GLYPHS
+                .iter()
+                .find(|glyph| glyph.char == char)
+                .unwrap();
+            for (dy, row) in glyph.rows.iter().enumerate() {
+                for dx in 0..glyph.width {
+                    if row & 1 << dx != 0 {
+                        image.pixels[((y + dy as i32) * image.size.x + x + dx as i32) as usize] =
+                            color;
+                    }
+                }
+            }
+            x += glyph.width as i32 + 1;
+        }
+    }
+
+    #[test]
+    fn reads_the_root_of_a_screenshot() {
+        let chart = analyze(&fixture("root")).unwrap();
+        assert_eq!(chart.path, "root");
+
+        // The text is a little see-through, so colors are only close.
+        let expected = [
+            ("gameRenderer", Color(0x4C, 0xD4, 0x7C), 6250, 6250),
+            ("tick", Color(0xC4, 0x5A, 0x3E), 3004, 3004),
+            ("unspecified", Color(0x5F, 0x7A, 0xE6), 746, 746),
+        ];
+        assert_eq!(chart.wedges.len(), expected.len());
+        for (wedge, (label, color, percent, global)) in chart.wedges.iter().zip(expected) {
+            assert_eq!(
+                (wedge.label.as_str(), wedge.percent, wedge.global),
+                (label, percent, global)
+            );
+            let channels = |color: Color| [color.0, color.1, color.2];
+            for (a, b) in channels(wedge.color).into_iter().zip(channels(color)) {
+                assert!(
+                    a.abs_diff(b) <= 10,
+                    "{:?} instead of {:?}",
+                    wedge.color,
+                    color
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reads_a_section_from_the_pie_region() {
+        let image = fixture("level");
+        assert_eq!(image.size, SIZE);
+
+        let chart = analyze(&image).unwrap();
+        assert_eq!(chart.path, "root.gameRenderer.level");
+        assert_eq!(
+            chart
+                .wedges
+                .iter()
+                .map(|wedge| (wedge.label.as_str(), wedge.percent, wedge.global))
+                .collect::<Vec<_>>(),
+            [
+                ("entities", 5531, 2666),
+                ("blockentities", 2002, 965),
+                ("unspecified", 2467, 1189),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_no_chart_where_there_is_none() {
+        assert_eq!(analyze(&fixture("no_chart")), None);
+        assert_eq!(analyze(&Image::new(XY::new(20, 20))), None);
+    }
+
+    #[test]
+    fn skips_legend_lines_with_misread_shares() {
+        let mut image = Image::new(SIZE);
+        let left = SIZE.x - CENTER_FROM_CORNER.x - RADIUS;
+        let top = SIZE.y - CENTER_FROM_CORNER.y + LEGEND;
+        let lines = [
+            ("[1] gameRenderer 62.50% 62.50%", 0x4CD47C),
+            ("[2] tick 3?.04% 30.04%", 0xC45A3E),
+            ("[3] unspecified 7.46% 7.46%", 0x5F7AE6),
+        ];
+        for (i, (text, color)) in lines.into_iter().enumerate() {
+            draw(&mut image, left, top + i as i32 * LINE_HEIGHT, text, color);
+        }
+
+        let chart = analyze(&image).unwrap();
+        assert_eq!(
+            chart
+                .wedges
+                .iter()
+                .map(|wedge| (wedge.label.as_str(), wedge.percent, wedge.global))
+                .collect::<Vec<_>>(),
+            [("gameRenderer", 6250, 6250), ("unspecified", 746, 746)]
+        );
+    }
+
+    #[test]
+    fn reads_words_of_a_line() {
+        let mut image = Image::new(XY::new(120, 10));
+        draw(&mut image, 2, 1, "[3] tick 45.12%", 0xE0E0E0);
+
+        assert_eq!(read_line(&image, 2, 1, 120, 0xE0E0E0), "[3] tick 45.12%");
+        // Only text of the line's color is read.
+        assert_eq!(read_line(&image, 2, 1, 120, 0x40A040), "");
+    }
+
+    #[test]
+    fn replaces_unknown_glyphs() {
+        let mut image = Image::new(XY::new(40, 10));
+        draw(&mut image, 0, 0, "ab", 0xFFFFFF);
+        for y in 0..8 {
+            image.pixels[(y * 40 + 20) as usize] = 0xFFFFFF;
+            image.pixels[(y * 40 + 21) as usize] = 0xFFFFFF;
+        }
+
+        assert_eq!(read_line(&image, 0, 0, 40, 0xFFFFFF), "ab \u{FFFD}");
+    }
+
+    #[test]
+    fn parses_percentages() {
+        assert_eq!(parse_percent("12.34%"), Some(1234));
+        assert_eq!(parse_percent("100.00%"), Some(10000));
+        assert_eq!(parse_percent("0.05%"), Some(5));
+        assert_eq!(parse_percent("12.3%"), None);
+        assert_eq!(parse_percent("12.34"), None);
+        assert_eq!(parse_percent("12%"), None);
+        assert_eq!(parse_percent("x.34%"), None);
+    }
+
+    #[test]
+    fn formats_percentages_like_the_game() {
+        assert_eq!(PieChart::percent(1234), "12.34%");
+        assert_eq!(PieChart::percent(5), "0.05%");
+    }
+}
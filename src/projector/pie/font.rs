@@ -0,0 +1,147 @@
+use std::sync::LazyLock;
+
+/// A glyph of the game's default font, one bit per pixel with bit `x` of
+/// row `y` set where it's drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Glyph {
+    pub char: char,
+    pub width: usize,
+    pub rows: [u8; 8],
+}
+
+/// Characters of the default font (`ascii.png`) that show up in the pie
+/// chart, as rows from the top. Rows past the seventh are descenders.
+const SHAPES: &[(char, &str)] = &[
+    ('0', ".###. #...# #..## #.#.# ##..# #...# .###."),
+    ('1', "..#.. .##.. ..#.. ..#.. ..#.. ..#.. #####"),
+    ('2', ".###. #...# ....# ..##. .#... #...# #####"),
+    ('3', ".###. #...# ....# ..##. ....# #...# .###."),
+    ('4', "...## ..#.# .#..# #...# ##### ....# ....#"),
+    ('5', "##### #.... ####. ....# ....# #...# .###."),
+    ('6', "..##. .#... #.... ####. #...# #...# .###."),
+    ('7', "##### #...# ....# ...#. ..#.. ..#.. ..#.."),
+    ('8', ".###. #...# #...# .###. #...# #...# .###."),
+    ('9', ".###. #...# #...# .#### ....# ...#. .##.."),
+    ('.', ". . . . . # #"),
+    (':', ". # # . . # #"),
+    ('%', "#...# #..#. ...#. ..#.. .#... .#..# #...#"),
+    ('[', "### #.. #.. #.. #.. #.. ###"),
+    (']', "### ..# ..# ..# ..# ..# ###"),
+    ('?', ".###. #...# ....# ...#. ..#.. ..... ..#.."),
+    ('-', "..... ..... ..... ##### ..... ..... ....."),
+    ('_', "..... ..... ..... ..... ..... ..... ..... #####"),
+    ('a', "..... ..... .###. ....# .#### #...# .####"),
+    ('b', "#.... #.... #.##. ##..# #...# #...# ####."),
+    ('c', "..... ..... .###. #...# #.... #...# .###."),
+    ('d', "....# ....# .##.# #..## #...# #...# .####"),
+    ('e', "..... ..... .###. #...# ##### #.... .####"),
+    ('f', "..## .#.. #### .#.. .#.. .#.. .#.."),
+    ('g', "..... ..... .#### #...# #...# .#### ....# ####."),
+    ('h', "#.... #.... #.##. ##..# #...# #...# #...#"),
+    ('i', "# . # # # # #"),
+    ('j', "....# ..... ....# ....# ....# #...# #...# .###."),
+    ('k', "#... #... #..# #.#. ##.. #.#. #..#"),
+    ('l', "#. #. #. #. #. #. .#"),
+    ('m', "..... ..... ##.#. #.#.# #.#.# #...# #...#"),
+    ('n', "..... ..... ####. #...# #...# #...# #...#"),
+    ('o', "..... ..... .###. #...# #...# #...# .###."),
+    ('p', "..... ..... #.##. ##..# #...# ####. #.... #...."),
+    ('q', "..... ..... .##.# #..## #...# .#### ....# ....#"),
+    ('r', "..... ..... #.##. ##..# #.... #.... #...."),
+    ('s', "..... ..... .#### #.... .###. ....# ####."),
+    ('t', ".#. .#. ### .#. .#. .#. ..#"),
+    ('u', "..... ..... #...# #...# #...# #...# .####"),
+    ('v', "..... ..... #...# #...# #...# .#.#. ..#.."),
+    ('w', "..... ..... #...# #...# #.#.# #.#.# .####"),
+    ('x', "..... ..... #...# .#.#. ..#.. .#.#. #...#"),
+    ('y', "..... ..... #...# #...# #...# .#### ....# ####."),
+    ('z', "..... ..... ##### ...#. ..#.. .#... #####"),
+    ('A', ".###. #...# ##### #...# #...# #...# #...#"),
+    ('B', "####. #...# ####. #...# #...# #...# ####."),
+    ('C', ".###. #...# #.... #.... #.... #...# .###."),
+    ('D', "####. #...# #...# #...# #...# #...# ####."),
+    ('E', "##### #.... ###.. #.... #.... #.... #####"),
+    ('F', "##### #.... ###.. #.... #.... #.... #...."),
+    ('G', ".#### #.... #..## #...# #...# #...# .###."),
+    ('H', "#...# #...# ##### #...# #...# #...# #...#"),
+    ('I', "### .#. .#. .#. .#. .#. ###"),
+    ('J', "....# ....# ....# ....# ....# #...# .###."),
+    ('K', "#...# #..#. ###.. #..#. #...# #...# #...#"),
+    ('L', "#.... #.... #.... #.... #.... #.... #####"),
+    ('M', "#...# ##.## #.#.# #...# #...# #...# #...#"),
+    ('N', "#...# ##..# #.#.# #..## #...# #...# #...#"),
+    ('O', ".###. #...# #...# #...# #...# #...# .###."),
+    ('P', "####. #...# ####. #.... #.... #.... #...."),
+    ('Q', ".###. #...# #...# #...# #...# #..#. .##.#"),
+    ('R', "####. #...# ####. #...# #...# #...# #...#"),
+    ('S', ".#### #.... .###. ....# ....# #...# .###."),
+    ('T', "##### ..#.. ..#.. ..#.. ..#.. ..#.. ..#.."),
+    ('U', "#...# #...# #...# #...# #...# #...# .###."),
+    ('V', "#...# #...# #...# #...# .#.#. .#.#. ..#.."),
+    ('W', "#...# #...# #...# #...# #.#.# ##.## #...#"),
+    ('X', "#...# .#.#. ..#.. .#.#. #...# #...# #...#"),
+    ('Y', "#...# .#.#. ..#.. ..#.. ..#.. ..#.. ..#.."),
+    ('Z', "##### ....# ...#. ..#.. .#... #.... #####"),
+];
+
+pub static GLYPHS: LazyLock<Vec<Glyph>> = LazyLock::new(|| {
+    SHAPES
+        .iter()
+        .map(|&(char, shape)| {
+            let mut glyph = Glyph {
+                char,
+                width: 0,
+                rows: [0; 8],
+            };
+            for (y, row) in shape.split(' ').enumerate() {
+                glyph.width = glyph.width.max(row.len());
+                for (x, pixel) in row.bytes().enumerate() {
+                    if pixel == b'#' {
+                        glyph.rows[y] |= 1 << x;
+                    }
+                }
+            }
+            glyph
+        })
+        .collect()
+});
+
+/// The character drawn as `rows`, `width` pixels wide.
+pub fn find(width: usize, rows: [u8; 8]) -> Option<char> {
+    GLYPHS
+        .iter()
+        .find(|glyph| glyph.width == width && glyph.rows == rows)
+        .map(|glyph| glyph.char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_every_glyph_by_its_shape() {
+        for glyph in GLYPHS.iter() {
+            assert_eq!(find(glyph.width, glyph.rows), Some(glyph.char));
+        }
+    }
+
+    #[test]
+    fn glyphs_are_told_apart() {
+        for (i, a) in GLYPHS.iter().enumerate() {
+            for b in &GLYPHS[i + 1..] {
+                assert!(
+                    a.width != b.width || a.rows != b.rows,
+                    "{} and {} look the same",
+                    a.char,
+                    b.char
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_shapes_are_not_found() {
+        assert_eq!(find(2, [0b11; 8]), None);
+        assert_eq!(find(6, GLYPHS[0].rows), None);
+    }
+}
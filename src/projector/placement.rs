@@ -0,0 +1,155 @@
+use crate::config::{
+    projector::{Placement, Side},
+    xy::XY,
+};
+
+/// The horizontal span beside the game window on `side`, clamped to the
+/// monitor. The whole monitor when the game window is elsewhere or leaves no
+/// space on that side.
+fn gap(side: Side, monitor: (XY, XY), game: Option<(XY, XY)>) -> (i32, i32) {
+    let (monitor_position, monitor_size) = monitor;
+    let monitor_left = monitor_position.x;
+    let monitor_right = monitor_position.x + monitor_size.x;
+
+    let (left, right) = match (game, side) {
+        (Some((position, _)), Side::Left) => (monitor_left, position.x),
+        (Some((position, size)), Side::Right) => (position.x + size.x, monitor_right),
+        (None, _) => (monitor_left, monitor_right),
+    };
+    let left = left.clamp(monitor_left, monitor_right);
+    let right = right.clamp(monitor_left, monitor_right);
+
+    if right > left {
+        (left, right)
+    } else {
+        (monitor_left, monitor_right)
+    }
+}
+
+/// Works out the screen rect of a projector on `monitor`. `game` is the rect
+/// of the game window if it's on the same monitor.
+pub fn place(placement: Placement, monitor: (XY, XY), game: Option<(XY, XY)>) -> (XY, XY) {
+    let (monitor_position, monitor_size) = monitor;
+
+    match placement {
+        Placement::Beside { side, height } => {
+            let (left, right) = gap(side, monitor, game);
+            let height = height.clamp(0, monitor_size.y);
+
+            (
+                XY::new(left, monitor_position.y + (monitor_size.y - height) / 2),
+                XY::new(right - left, height),
+            )
+        }
+        Placement::Fill { side } => {
+            let (left, right) = gap(side, monitor, game);
+
+            (
+                XY::new(left, monitor_position.y),
+                XY::new(right - left, monitor_size.y),
+            )
+        }
+        Placement::Custom { position, size } => (monitor_position + position, size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 1920x1080 monitor right of the primary one.
+    const MONITOR: (XY, XY) = (XY { x: 1920, y: 0 }, XY { x: 1920, y: 1080 });
+
+    fn game(x: i32, width: i32) -> Option<(XY, XY)> {
+        Some((XY::new(1920 + x, 0), XY::new(width, 1080)))
+    }
+
+    #[test]
+    fn fills_the_gap_beside_the_game() {
+        let placement = Placement::Fill { side: Side::Left };
+        assert_eq!(
+            place(placement, MONITOR, game(800, 320)),
+            (XY::new(1920, 0), XY::new(800, 1080))
+        );
+
+        let placement = Placement::Fill { side: Side::Right };
+        assert_eq!(
+            place(placement, MONITOR, game(800, 320)),
+            (XY::new(3040, 0), XY::new(800, 1080))
+        );
+    }
+
+    #[test]
+    fn centers_projectors_beside_the_game() {
+        let placement = Placement::Beside {
+            side: Side::Right,
+            height: 400,
+        };
+        assert_eq!(
+            place(placement, MONITOR, game(800, 320)),
+            (XY::new(3040, 340), XY::new(800, 400))
+        );
+    }
+
+    #[test]
+    fn uses_the_whole_monitor_without_the_game() {
+        let placement = Placement::Fill { side: Side::Left };
+        assert_eq!(place(placement, MONITOR, None), MONITOR);
+    }
+
+    #[test]
+    fn clamps_the_gap_to_the_monitor() {
+        // The game hangs off the right edge of the monitor.
+        let placement = Placement::Fill { side: Side::Left };
+        assert_eq!(
+            place(placement, MONITOR, game(1800, 320)),
+            (XY::new(1920, 0), XY::new(1800, 1080))
+        );
+
+        // Or off the left one.
+        let placement = Placement::Fill { side: Side::Right };
+        assert_eq!(
+            place(placement, MONITOR, game(-100, 320)),
+            (XY::new(2140, 0), XY::new(1700, 1080))
+        );
+    }
+
+    #[test]
+    fn uses_the_whole_monitor_without_a_gap() {
+        // The game touches the edge on the projector's side.
+        let placement = Placement::Fill { side: Side::Left };
+        assert_eq!(place(placement, MONITOR, game(0, 320)), MONITOR);
+
+        // Covers the whole monitor.
+        let placement = Placement::Fill { side: Side::Right };
+        assert_eq!(place(placement, MONITOR, game(-10, 1940)), MONITOR);
+
+        // Or is past the edge on the other side.
+        let placement = Placement::Fill { side: Side::Left };
+        assert_eq!(place(placement, MONITOR, game(-400, 320)), MONITOR);
+    }
+
+    #[test]
+    fn clamps_the_height_to_the_monitor() {
+        let placement = Placement::Beside {
+            side: Side::Left,
+            height: 4000,
+        };
+        assert_eq!(
+            place(placement, MONITOR, game(800, 320)),
+            (XY::new(1920, 0), XY::new(800, 1080))
+        );
+    }
+
+    #[test]
+    fn custom_rects_are_relative_to_the_monitor() {
+        let placement = Placement::Custom {
+            position: XY::new(10, 20),
+            size: XY::new(300, 200),
+        };
+        assert_eq!(
+            place(placement, MONITOR, game(800, 320)),
+            (XY::new(1930, 20), XY::new(300, 200))
+        );
+    }
+}
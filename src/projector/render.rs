@@ -0,0 +1,520 @@
+use windows::Win32::{
+    Foundation::RECT,
+    Graphics::Gdi::{
+        BI_RGB, BITMAPINFO, BITMAPINFOHEADER, BLACK_BRUSH, BitBlt, COLORONCOLOR,
+        CreateCompatibleDC, CreateDIBSection, DIB_RGB_COLORS, DeleteDC, DeleteObject, FillRect,
+        GetStockObject, HALFTONE, HBRUSH, HDC, SRCCOPY, SelectObject, SetBrushOrgEx,
+        SetDIBitsToDevice, SetStretchBltMode, StretchBlt,
+    },
+};
+
+use crate::{
+    config::{
+        projector::{Blit, Filter, Scaling},
+        xy::XY,
+    },
+    projector::crop::{self, CropRegions, Region},
+};
+
+/// Something blits can be drawn with.
+pub trait Renderer {
+    /// Fills `region` of the target with black.
+    fn clear(&mut self, region: Region);
+    /// Copies `source` to `destination`, resizing it with `filter`.
+    fn blit(&mut self, source: Region, destination: Region, filter: Filter);
+}
+
+/// Draws `blits` from a game window whose client area is `client_size` onto
+/// a projector of `projector_size`.
+pub fn render(
+    renderer: &mut impl Renderer,
+    blits: &[Blit],
+    regions: CropRegions,
+    client_size: XY,
+    projector_size: XY,
+) {
+    for blit in blits {
+        let source = crop::source_region(blit.source, regions, client_size);
+        let destination = crop::destination_region(blit, source.size, projector_size);
+
+        if blit.scaling == Scaling::Integer {
+            renderer.clear(crop::destination_area(blit.destination, projector_size));
+        }
+        renderer.blit(source, destination, blit.filter);
+    }
+}
+
+/// An image in memory, as rows of `0x00RRGGBB` pixels from the top down.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub size: XY,
+    pub pixels: Vec<u32>,
+}
+
+impl Image {
+    pub fn new(size: XY) -> Self {
+        Self {
+            size,
+            pixels: vec![0; (size.x.max(0) * size.y.max(0)) as usize],
+        }
+    }
+
+    /// The pixel at `(x, y)`, clamped to the edges of the image.
+    pub fn get(&self, x: i32, y: i32) -> u32 {
+        let x = x.clamp(0, self.size.x - 1);
+        let y = y.clamp(0, self.size.y - 1);
+
+        self.pixels[(y * self.size.x + x) as usize]
+    }
+
+    fn fill(&mut self, region: Region, color: u32) {
+        let (left, top, right, bottom) = self.clip(region);
+
+        for y in top..bottom {
+            let row = (y * self.size.x) as usize;
+            self.pixels[row + left as usize..row + right as usize].fill(color);
+        }
+    }
+
+    /// The part of `region` inside the image, as left, top, right, bottom.
+    fn clip(&self, region: Region) -> (i32, i32, i32, i32) {
+        (
+            region.position.x.clamp(0, self.size.x),
+            region.position.y.clamp(0, self.size.y),
+            (region.position.x + region.size.x).clamp(0, self.size.x),
+            (region.position.y + region.size.y).clamp(0, self.size.y),
+        )
+    }
+
+    fn bitmap_info(size: XY) -> BITMAPINFO {
+        BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: size.x,
+                // Negative for rows from the top down.
+                biHeight: -size.y,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..BITMAPINFOHEADER::default()
+            },
+            ..BITMAPINFO::default()
+        }
+    }
+
+    /// Copies `region` of the device context into a new image.
+    pub fn capture(hdc: HDC, region: Region) -> Option<Self> {
+        if region.size.x <= 0 || region.size.y <= 0 {
+            return None;
+        }
+
+        unsafe {
+            let memory = CreateCompatibleDC(Some(hdc));
+            let info = Self::bitmap_info(region.size);
+            let mut bits = std::ptr::null_mut();
+            let Ok(bitmap) = CreateDIBSection(
+                Some(memory),
+                &raw const info,
+                DIB_RGB_COLORS,
+                &raw mut bits,
+                None,
+                0,
+            ) else {
+                let _ = DeleteDC(memory);
+                return None;
+            };
+
+            let old = SelectObject(memory, bitmap.into());
+            let copied = BitBlt(
+                memory,
+                0,
+                0,
+                region.size.x,
+                region.size.y,
+                Some(hdc),
+                region.position.x,
+                region.position.y,
+                SRCCOPY,
+            );
+
+            let image = copied.ok().map(|()| {
+                let len = (region.size.x * region.size.y) as usize;
+                Self {
+                    size: region.size,
+                    pixels: std::slice::from_raw_parts(bits as *const u32, len).to_vec(),
+                }
+            });
+
+            SelectObject(memory, old);
+            let _ = DeleteObject(bitmap.into());
+            let _ = DeleteDC(memory);
+
+            image
+        }
+    }
+
+    /// Loads an uncompressed 24 or 32 bit BMP file, such as the tests'
+    /// stored captures.
+    #[cfg(test)]
+    pub fn read_bmp(path: &std::path::Path) -> std::io::Result<Self> {
+        let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        let data = std::fs::read(path)?;
+        let u16_at = |i: usize| data.get(i..i + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+        let u32_at = |i: usize| {
+            data.get(i..i + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+
+        if data.get(0..2) != Some(b"BM") {
+            return Err(invalid("not a BMP file"));
+        }
+        let (Some(offset), Some(width), Some(height), Some(bits), Some(compression)) =
+            (u32_at(10), u32_at(18), u32_at(22), u16_at(28), u32_at(30))
+        else {
+            return Err(invalid("truncated BMP header"));
+        };
+        if compression != BI_RGB.0 || !(bits == 24 || bits == 32) {
+            return Err(invalid(
+                "only uncompressed 24 and 32 bit BMPs are supported",
+            ));
+        }
+
+        let width = width as i32;
+        // Positive heights are stored from the bottom up.
+        let height = height as i32;
+        let bottom_up = height > 0;
+        let mut image = Self::new(XY::new(width, height.abs()));
+        let bytes = bits as usize / 8;
+        let stride = (width as usize * bytes).div_ceil(4) * 4;
+
+        for y in 0..image.size.y {
+            let row = if bottom_up { image.size.y - 1 - y } else { y } as usize;
+            for x in 0..width {
+                let i = offset as usize + row * stride + x as usize * bytes;
+                let Some(&[b, g, r]) = data.get(i..i + 3) else {
+                    return Err(invalid("truncated BMP pixels"));
+                };
+                image.pixels[(y * width + x) as usize] =
+                    (r as u32) << 16 | (g as u32) << 8 | b as u32;
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Draws the image onto the device context at `position`.
+    pub fn present(&self, hdc: HDC, position: XY) {
+        let info = Self::bitmap_info(self.size);
+
+        unsafe {
+            SetDIBitsToDevice(
+                hdc,
+                position.x,
+                position.y,
+                self.size.x as u32,
+                self.size.y as u32,
+                0,
+                0,
+                0,
+                self.size.y as u32,
+                self.pixels.as_ptr().cast(),
+                &raw const info,
+                DIB_RGB_COLORS,
+            );
+        }
+    }
+}
+
+/// Blends two `0x00RRGGBB` pixels, `t` being the weight of `b` out of 256.
+fn lerp(a: u32, b: u32, t: u32) -> u32 {
+    let channel = |shift: u32| {
+        let a = (a >> shift) & 0xFF;
+        let b = (b >> shift) & 0xFF;
+        ((a * (256 - t) + b * t) >> 8) << shift
+    };
+
+    channel(16) | channel(8) | channel(0)
+}
+
+/// Scales between images in memory, the same way for every platform.
+pub struct SoftwareRenderer<'a> {
+    pub source: &'a Image,
+    pub target: &'a mut Image,
+}
+
+impl Renderer for SoftwareRenderer<'_> {
+    fn clear(&mut self, region: Region) {
+        self.target.fill(region, 0);
+    }
+
+    fn blit(&mut self, source: Region, destination: Region, filter: Filter) {
+        if source.size.x <= 0
+            || source.size.y <= 0
+            || self.source.size.x <= 0
+            || self.source.size.y <= 0
+        {
+            return;
+        }
+
+        let (left, top, right, bottom) = self.target.clip(destination);
+
+        // Source coordinates in 1/256 pixels, sampled at pixel centers. Each
+        // is worked out from its own offset, as adding up a rounded step
+        // drifts by whole pixels across a large stretch.
+        let sample = |offset: i32, source: i32, destination: i32, origin: i32| {
+            let center =
+                (2 * offset as i64 + 1) * ((source as i64) << 8) / (2 * destination.max(1) as i64);
+            (origin << 8) + center as i32 - 128
+        };
+
+        for y in top..bottom {
+            let sy = sample(
+                y - destination.position.y,
+                source.size.y,
+                destination.size.y,
+                source.position.y,
+            );
+            let row = (y * self.target.size.x) as usize;
+
+            for x in left..right {
+                let sx = sample(
+                    x - destination.position.x,
+                    source.size.x,
+                    destination.size.x,
+                    source.position.x,
+                );
+
+                self.target.pixels[row + x as usize] = match filter {
+                    Filter::Nearest => self.source.get((sx + 128) >> 8, (sy + 128) >> 8),
+                    Filter::Smooth => {
+                        let (x0, y0) = (sx >> 8, sy >> 8);
+                        let (tx, ty) = ((sx & 0xFF) as u32, (sy & 0xFF) as u32);
+
+                        let top = lerp(self.source.get(x0, y0), self.source.get(x0 + 1, y0), tx);
+                        let bottom = lerp(
+                            self.source.get(x0, y0 + 1),
+                            self.source.get(x0 + 1, y0 + 1),
+                            tx,
+                        );
+                        lerp(top, bottom, ty)
+                    }
+                };
+            }
+        }
+    }
+}
+
+/// Stretches straight from one device context to another.
+pub struct GdiRenderer {
+    pub source: HDC,
+    pub target: HDC,
+}
+
+impl Renderer for GdiRenderer {
+    fn clear(&mut self, region: Region) {
+        unsafe {
+            FillRect(
+                self.target,
+                &RECT {
+                    left: region.position.x,
+                    top: region.position.y,
+                    right: region.position.x + region.size.x,
+                    bottom: region.position.y + region.size.y,
+                } as *const RECT,
+                HBRUSH(GetStockObject(BLACK_BRUSH).0),
+            );
+        }
+    }
+
+    fn blit(&mut self, source: Region, destination: Region, filter: Filter) {
+        unsafe {
+            match filter {
+                Filter::Nearest => {
+                    SetStretchBltMode(self.target, COLORONCOLOR);
+                }
+                Filter::Smooth => {
+                    // HALFTONE needs the brush origin reset after it's set.
+                    SetStretchBltMode(self.target, HALFTONE);
+                    let _ = SetBrushOrgEx(self.target, 0, 0, None);
+                }
+            }
+
+            let _ = StretchBlt(
+                self.target,
+                destination.position.x,
+                destination.position.y,
+                destination.size.x,
+                destination.size.y,
+                Some(self.source),
+                source.position.x,
+                source.position.y,
+                source.size.x,
+                source.size.y,
+                SRCCOPY,
+            );
+        }
+    }
+}
+
+/// Copies each source out of a device context and scales it in memory.
+pub struct CapturingRenderer<'a> {
+    pub source: HDC,
+    pub target: &'a mut Image,
+}
+
+impl Renderer for CapturingRenderer<'_> {
+    fn clear(&mut self, region: Region) {
+        self.target.fill(region, 0);
+    }
+
+    fn blit(&mut self, source: Region, destination: Region, filter: Filter) {
+        let Some(captured) = Image::capture(self.source, source) else {
+            return;
+        };
+
+        SoftwareRenderer {
+            source: &captured,
+            target: self.target,
+        }
+        .blit(
+            Region {
+                position: XY::new(0, 0),
+                size: source.size,
+            },
+            destination,
+            filter,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(size: XY, pixels: &[u32]) -> Image {
+        Image {
+            size,
+            pixels: pixels.to_vec(),
+        }
+    }
+
+    fn scale(source: &Image, size: XY, filter: Filter) -> Image {
+        let mut target = Image::new(size);
+        SoftwareRenderer {
+            source,
+            target: &mut target,
+        }
+        .blit(
+            Region {
+                position: XY::new(0, 0),
+                size: source.size,
+            },
+            Region {
+                position: XY::new(0, 0),
+                size,
+            },
+            filter,
+        );
+        target
+    }
+
+    #[test]
+    fn nearest_picks_the_pixel_under_each_center() {
+        let source = image(XY::new(3, 1), &[1, 2, 3]);
+
+        // A step of 3/1000 would round down to nothing in 1/256 pixels.
+        let target = scale(&source, XY::new(1000, 1), Filter::Nearest);
+        for (x, &pixel) in target.pixels.iter().enumerate() {
+            assert_eq!(pixel, (2 * x as u32 + 1) * 3 / 2000 + 1, "at {}", x);
+        }
+
+        let target = scale(&source, XY::new(6, 2), Filter::Nearest);
+        assert_eq!(target.pixels, [1, 1, 2, 2, 3, 3, 1, 1, 2, 2, 3, 3]);
+    }
+
+    #[test]
+    fn nearest_stays_aligned_across_a_tall_strip() {
+        // The eye projector stretches a 60 pixel strip to a whole monitor.
+        let pixels: Vec<u32> = (0..60).collect();
+        let source = image(XY::new(60, 1), &pixels);
+
+        let target = scale(&source, XY::new(1000, 1), Filter::Nearest);
+        assert_eq!(target.pixels[999], 59);
+        assert_eq!(target.pixels[500], 30);
+        assert_eq!(target.pixels[499], 29);
+    }
+
+    #[test]
+    fn nearest_downscales_by_skipping_pixels() {
+        let source = image(XY::new(4, 1), &[1, 2, 3, 4]);
+
+        let target = scale(&source, XY::new(2, 1), Filter::Nearest);
+        assert_eq!(target.pixels, [2, 4]);
+    }
+
+    #[test]
+    fn smooth_blends_between_pixel_centers() {
+        let source = image(XY::new(2, 1), &[0x000000, 0xFFFFFF]);
+
+        let target = scale(&source, XY::new(4, 1), Filter::Smooth);
+        assert_eq!(target.pixels, [0x000000, 0x3F3F3F, 0xBFBFBF, 0xFFFFFF]);
+    }
+
+    #[test]
+    fn smooth_keeps_flat_colors() {
+        let source = image(XY::new(2, 2), &[0x336699; 4]);
+
+        let target = scale(&source, XY::new(7, 5), Filter::Smooth);
+        assert!(target.pixels.iter().all(|&pixel| pixel == 0x336699));
+    }
+
+    #[test]
+    fn blits_are_clipped_to_the_target() {
+        let source = image(XY::new(2, 1), &[1, 2]);
+        let mut target = Image::new(XY::new(3, 1));
+        SoftwareRenderer {
+            source: &source,
+            target: &mut target,
+        }
+        .blit(
+            Region {
+                position: XY::new(0, 0),
+                size: source.size,
+            },
+            Region {
+                position: XY::new(-2, 0),
+                size: XY::new(4, 1),
+            },
+            Filter::Nearest,
+        );
+
+        assert_eq!(target.pixels, [2, 2, 0]);
+    }
+
+    #[test]
+    fn empty_sources_draw_nothing() {
+        for size in [XY::new(2, 0), XY::new(0, 2)] {
+            let source = Image::new(size);
+            let mut target = Image::new(XY::new(4, 2));
+            for filter in [Filter::Nearest, Filter::Smooth] {
+                SoftwareRenderer {
+                    source: &source,
+                    target: &mut target,
+                }
+                .blit(
+                    Region {
+                        position: XY::new(0, 0),
+                        size: XY::new(2, 2),
+                    },
+                    Region {
+                        position: XY::new(0, 0),
+                        size: XY::new(4, 2),
+                    },
+                    filter,
+                );
+            }
+
+            assert!(target.pixels.iter().all(|&pixel| pixel == 0));
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+use futures::{StreamExt, stream};
+use futures_channel::mpsc;
+use rhai::{
+    AST, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, ImmutableString, Map, Scope,
+    module_resolvers::DummyModuleResolver,
+};
+use windows::Win32::Foundation::RECT;
+
+use crate::{
+    config::{Config, Hotkey, PROJECT_DIR, xy::XY},
+    instance::registry::InstanceRegistry,
+    manager::{
+        Command,
+        event::{Event, EventBus},
+    },
+    watch,
+};
+
+fn scripts_dir() -> PathBuf {
+    PROJECT_DIR.config_dir().join("scripts")
+}
+
+fn script_paths() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(scripts_dir()) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn mode_name(mode: Option<Hotkey>) -> ImmutableString {
+    mode.map(Hotkey::name).unwrap_or("normal").into()
+}
+
+fn rect_map((position, size): (XY, XY)) -> Dynamic {
+    let mut map = Map::new();
+    map.insert("x".into(), (position.x as i64).into());
+    map.insert("y".into(), (position.y as i64).into());
+    map.insert("width".into(), (size.x as i64).into());
+    map.insert("height".into(), (size.y as i64).into());
+    map.into()
+}
+
+struct Script {
+    path: PathBuf,
+    ast: AST,
+    scope: Scope<'static>,
+}
+
+/// Runs the user's Rhai scripts from the `scripts` directory next to the
+/// config, calling their handlers as events come in.
+///
+/// Scripts may define any of:
+///
+/// - `on_mode_enter(mode)` and `on_mode_exit(mode)`, with `mode` one of
+///   `"tall"`, `"thin"`, `"wide"` or `"normal"`
+/// - `on_instance_found()`
+/// - `on_game_state(state)`, with `state` one of `"title"`, `"waiting"`,
+///   `"generating"`, `"previewing"`, `"inworld"`, `"wall"` or `"unknown"`
+/// - `on_log(event)`, with `event` one of `"world_joined"`, `"world_left"`,
+///   `"dimension"`, `"advancement"`, `"death"`, `"chat"` or the name of a
+///   configured log pattern
+/// - `on_hotkey(binding)`, with `binding` a mode name, `"back"`, `"reset"` or
+///   `"macro"`
+///
+/// and may call `set_mode(mode)`, `config()`, `monitor_rect()`,
+/// `instance_rect()` and `log(message)`.
+struct ScriptHost {
+    engine: Engine,
+    scripts: Vec<Script>,
+    events: EventBus,
+}
+
+impl ScriptHost {
+    fn new(
+        config: Arc<ArcSwap<Config>>,
+        instances: InstanceRegistry,
+        events: EventBus,
+        commands: mpsc::UnboundedSender<Command>,
+    ) -> Self {
+        let mut engine = Engine::new();
+
+        engine
+            .set_module_resolver(DummyModuleResolver::new())
+            .set_max_operations(1_000_000)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(1 << 16)
+            .set_max_array_size(1 << 12)
+            .set_max_map_size(1 << 12)
+            .disable_symbol("eval");
+
+        engine.on_print(|message| log::info!("[script] {}", message));
+        engine.on_debug(|message, _, position| log::debug!("[script] {}: {}", position, message));
+
+        engine.register_fn("log", |message: &str| log::info!("[script] {}", message));
+
+        engine.register_fn(
+            "set_mode",
+            move |mode: &str| -> Result<(), Box<EvalAltResult>> {
+                let mode = match mode {
+                    "normal" => None,
+                    name => Some(
+                        Hotkey::from_name(name).ok_or_else(|| format!("unknown mode: {}", name))?,
+                    ),
+                };
+
+                let _ = commands.unbounded_send(Command::SetMode(mode));
+
+                Ok(())
+            },
+        );
+
+        engine.register_fn("config", move || -> Result<Dynamic, Box<EvalAltResult>> {
+            rhai::serde::to_dynamic(config.load().as_ref())
+        });
+
+        engine.register_fn("monitor_rect", {
+            let instances = instances.clone();
+            move || -> Dynamic {
+                instances
+                    .active()
+                    .map(|instance| rect_map(instance.get_monitor_info()))
+                    .unwrap_or(Dynamic::UNIT)
+            }
+        });
+
+        engine.register_fn("instance_rect", move || -> Dynamic {
+            instances
+                .active()
+                .and_then(|instance| {
+                    let RECT {
+                        left,
+                        top,
+                        right,
+                        bottom,
+                    } = instance.get_window_rect().ok()?;
+
+                    Some(rect_map((
+                        XY::new(left, top),
+                        XY::new(right - left, bottom - top),
+                    )))
+                })
+                .unwrap_or(Dynamic::UNIT)
+        });
+
+        Self {
+            engine,
+            scripts: Vec::new(),
+            events,
+        }
+    }
+
+    fn report(events: &EventBus, path: &Path, error: impl Display) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let message = format!("{}: {}", name, error);
+
+        log::error!("Script error in {}", message);
+        events.publish(Event::ErrorRaised(message));
+    }
+
+    fn load(&self, path: PathBuf) -> Result<Script, (PathBuf, Box<EvalAltResult>)> {
+        let ast = match self.engine.compile_file(path.clone()) {
+            Ok(ast) => ast,
+            Err(e) => return Err((path, e)),
+        };
+
+        let mut scope = Scope::new();
+        if let Err(e) = self.engine.run_ast_with_scope(&mut scope, &ast) {
+            return Err((path, e));
+        }
+
+        Ok(Script { path, ast, scope })
+    }
+
+    fn reload(&mut self) {
+        log::info!("Loading scripts from {}", scripts_dir().display());
+
+        self.scripts.clear();
+
+        for path in script_paths() {
+            match self.load(path) {
+                Ok(script) => self.scripts.push(script),
+                Err((path, e)) => Self::report(&self.events, &path, e),
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str, args: impl FuncArgs + Clone) {
+        let Self {
+            engine,
+            scripts,
+            events,
+        } = self;
+
+        for script in scripts {
+            if !script.ast.iter_functions().any(|f| f.name == name) {
+                continue;
+            }
+
+            let options = CallFnOptions::new().eval_ast(false);
+            if let Err(e) = engine.call_fn_with_options::<Dynamic>(
+                options,
+                &mut script.scope,
+                &script.ast,
+                name,
+                args.clone(),
+            ) {
+                Self::report(events, &script.path, e);
+            }
+        }
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::ModeChanged { from, to, .. } => {
+                self.call("on_mode_exit", (mode_name(from),));
+                self.call("on_mode_enter", (mode_name(to),));
+            }
+            Event::InstanceAttached { .. } => {
+                self.call("on_instance_found", ());
+            }
+            Event::Log { event, .. } => {
+                self.call("on_log", (ImmutableString::from(event.name()),));
+            }
+            Event::GameStateChanged { state, .. } => {
+                self.call("on_game_state", (ImmutableString::from(state.name()),));
+            }
+            Event::HotkeyTriggered(binding) => {
+                self.call("on_hotkey", (ImmutableString::from(binding.name()),));
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn spawn(
+    config: Arc<ArcSwap<Config>>,
+    instances: InstanceRegistry,
+    events: EventBus,
+    commands: mpsc::UnboundedSender<Command>,
+) {
+    let received = events.subscribe().map(Some);
+    let changed = watch::spawn(Duration::from_millis(500), || {
+        watch::fingerprint(script_paths())
+    })
+    .map(|()| None);
+
+    std::thread::spawn(move || {
+        let mut host = ScriptHost::new(config, instances, events, commands);
+        host.reload();
+
+        let mut inputs = stream::select(received, changed);
+        futures::executor::block_on(async {
+            while let Some(input) = inputs.next().await {
+                match input {
+                    Some(event) => host.handle(event),
+                    None => host.reload(),
+                }
+            }
+        });
+    });
+}
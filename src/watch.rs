@@ -0,0 +1,47 @@
+use std::{path::PathBuf, thread, time::Duration, time::SystemTime};
+
+use futures_channel::mpsc;
+
+/// Modification times of a set of files, `None` for files that don't exist.
+pub type Fingerprint = Vec<(PathBuf, Option<SystemTime>)>;
+
+pub fn fingerprint(paths: impl IntoIterator<Item = PathBuf>) -> Fingerprint {
+    paths
+        .into_iter()
+        .map(|path| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+/// Polls `snapshot` every `interval` and signals whenever its result changes.
+/// The files wrinkle watches are few and small, so polling is simpler than
+/// change notifications and works the same for files that don't exist yet.
+///
+/// The polling thread exits once the receiver is dropped.
+pub fn spawn(
+    interval: Duration,
+    mut snapshot: impl FnMut() -> Fingerprint + Send + 'static,
+) -> mpsc::UnboundedReceiver<()> {
+    let (tx, rx) = mpsc::unbounded();
+
+    thread::spawn(move || {
+        let mut last = snapshot();
+
+        while !tx.is_closed() {
+            thread::sleep(interval);
+
+            let current = snapshot();
+            if current != last {
+                last = current;
+
+                if tx.unbounded_send(()).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    rx
+}
@@ -1,33 +1,301 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
 use std::str::FromStr;
 use std::sync::{Arc, Weak};
 
 use arc_swap::ArcSwap;
+use futures::{SinkExt, StreamExt};
 use iced::alignment::Vertical;
-use iced::widget::{Column, Row, button, column, container, row, space, text, text_input};
+use iced::widget::{
+    Column, Row, button, checkbox, column, container, pick_list, row, scrollable, space, text,
+    text_input,
+};
 use iced::{Background, Element, Length, Size, Subscription};
 
+use crate::calculator::{self, Candidate, Throw};
+use crate::config::per_mode::PerMode;
+use crate::config::projector::{
+    Anchor, Backend, Blit, Destination, Filter, Placement, ProjectorSettings, Scaling, Side, Source,
+};
 use crate::config::xy::XY;
-use crate::config::{self, Config, Hotkey};
-use crate::keylogger::KeyEvent;
+use crate::config::{self, Binding, Borderless, Config, Hotkey};
+use crate::game_state::GameState;
+use crate::instance::metadata::InstanceMetadata;
+use crate::instance::{self, Detection, MinecraftInstance};
+use crate::keylogger::KeyFilter;
 use crate::manager::Manager;
+use crate::manager::event::{Event, EventBus};
+use crate::mode::Transition;
+use crate::projector::eye::{self, EyeReading};
+use crate::projector::pacing::FrameStats;
+
+/// An instance as listed in the GUI.
+#[derive(Debug)]
+struct AttachedInstance {
+    instance: MinecraftInstance,
+    metadata: Arc<InstanceMetadata>,
+    state: GameState,
+}
 
 #[derive(Debug)]
 struct Window {
     old_config: Config,
     config: Arc<ArcSwap<Config>>,
+    events: EventBus,
+    instances: Vec<AttachedInstance>,
+    error: Option<String>,
     colors: [String; 2],
     thin: String,
     tall: String,
     wide: String,
-    changing: Option<Hotkey>,
+    mouse_speeds: PerMode<String>,
+    changing: Option<Binding>,
+    detections: Vec<Detection>,
+    /// Index of the projector being edited.
+    projector: usize,
+    projector_height: String,
+    projector_position: String,
+    projector_size: String,
+    projector_monitor: String,
+    projector_fps: String,
+    projector_stats: BTreeMap<String, FrameStats>,
+    /// The ender eye last found in tall mode.
+    eye: Option<EyeReading>,
+    blits: Vec<BlitText>,
+    throw: ThrowText,
+    throws: Vec<Throw>,
+    candidates: Vec<Candidate>,
+}
+
+/// The text fields of a throw being entered, kept as typed.
+#[derive(Clone, Debug, Default)]
+struct ThrowText {
+    x: String,
+    z: String,
+    angle: String,
+    pixels: String,
+    /// The correction of the eye reading the pixels were taken from, more
+    /// exact than working it out at the default field of view.
+    eye_correction: Option<f64>,
+}
+
+/// The text fields of a blit being edited, kept as typed.
+#[derive(Clone, Debug, Default)]
+struct BlitText {
+    offset: String,
+    size: String,
+    destination: String,
+}
+
+impl BlitText {
+    fn of_projector(projector: Option<&ProjectorSettings>) -> Vec<Self> {
+        projector
+            .into_iter()
+            .flat_map(|projector| &projector.blits)
+            .map(|blit| {
+                let (offset, size) = match blit.source {
+                    Source::Rect { offset, size, .. } => {
+                        (format!("{}, {}", offset.x, offset.y), size.to_string())
+                    }
+                    _ => Default::default(),
+                };
+
+                Self {
+                    offset,
+                    size,
+                    destination: blit.destination.to_string(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The kind of a projector's [`Placement`], for picking in the GUI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PlacementKind {
+    Beside,
+    Fill,
+    Custom,
+}
+
+impl PlacementKind {
+    const ALL: [PlacementKind; 3] = [
+        PlacementKind::Beside,
+        PlacementKind::Fill,
+        PlacementKind::Custom,
+    ];
+}
+
+impl From<Placement> for PlacementKind {
+    fn from(value: Placement) -> Self {
+        match value {
+            Placement::Beside { .. } => PlacementKind::Beside,
+            Placement::Fill { .. } => PlacementKind::Fill,
+            Placement::Custom { .. } => PlacementKind::Custom,
+        }
+    }
+}
+
+impl Display for PlacementKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlacementKind::Beside => f.write_str("Beside"),
+            PlacementKind::Fill => f.write_str("Fill gap"),
+            PlacementKind::Custom => f.write_str("Custom"),
+        }
+    }
+}
+
+/// The kind of a blit's [`Source`], for picking in the GUI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SourceKind {
+    Rect,
+    Pie,
+    Entities,
+}
+
+impl SourceKind {
+    const ALL: [SourceKind; 3] = [SourceKind::Rect, SourceKind::Pie, SourceKind::Entities];
+}
+
+impl From<Source> for SourceKind {
+    fn from(value: Source) -> Self {
+        match value {
+            Source::Rect { .. } => SourceKind::Rect,
+            Source::Pie => SourceKind::Pie,
+            Source::Entities => SourceKind::Entities,
+        }
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+impl Display for SourceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceKind::Rect => f.write_str("Rect"),
+            SourceKind::Pie => f.write_str("Pie chart"),
+            SourceKind::Entities => f.write_str("Entities"),
+        }
+    }
+}
+
+/// Parses `x, y` with either value possibly negative.
+fn parse_offset(s: &str) -> Option<XY> {
+    let (x, y) = s.split_once(',')?;
+    Some(XY::new(
+        x.trim().parse::<i32>().ok()?,
+        y.trim().parse::<i32>().ok()?,
+    ))
+}
+
+/// Parses `x, y, width, height` fractions.
+fn parse_destination(s: &str) -> Option<Destination> {
+    let values = s
+        .split(',')
+        .map(|value| value.trim().parse::<f32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let [x, y, width, height] = values[..] else {
+        return None;
+    };
+
+    Some(Destination {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+/// "Enhance pointer precision" as shown in the GUI, `Default` leaving the
+/// user's own setting alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Precision {
+    Default,
+    On,
+    Off,
+}
+
+impl Precision {
+    const ALL: [Precision; 3] = [Precision::Default, Precision::On, Precision::Off];
+}
+
+impl From<Option<bool>> for Precision {
+    fn from(value: Option<bool>) -> Self {
+        match value {
+            None => Precision::Default,
+            Some(true) => Precision::On,
+            Some(false) => Precision::Off,
+        }
+    }
+}
+
+impl From<Precision> for Option<bool> {
+    fn from(value: Precision) -> Self {
+        match value {
+            Precision::Default => None,
+            Precision::On => Some(true),
+            Precision::Off => Some(false),
+        }
+    }
+}
+
+impl Display for Precision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Precision::Default => f.write_str("Default"),
+            Precision::On => f.write_str("On"),
+            Precision::Off => f.write_str("Off"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 enum Message {
-    Change(Hotkey),
+    Change(Binding),
+    SetTransition(Transition),
+    SetBorderless(Borderless),
+    SetHooksEnabled(bool),
+    SetRequireInWorld(bool),
+    SetResetOutsideWorld(bool),
     SetColor(usize, String),
     SetResolution(Hotkey, String),
-    KeyEvent(KeyEvent),
+    SetMouseSpeed(Hotkey, String),
+    SetPointerPrecision(Hotkey, Precision),
+    SelectProjector(String),
+    AddProjector,
+    RemoveProjector,
+    SetProjectorPlacement(PlacementKind),
+    SetProjectorSide(Side),
+    SetProjectorHeight(String),
+    SetProjectorPosition(String),
+    SetProjectorSize(String),
+    SetProjectorMonitor(String),
+    SetProjectorShownIn(Hotkey, bool),
+    SetProjectorTopmost(bool),
+    SetProjectorRuler(bool),
+    SetProjectorIsolation(bool),
+    SetProjectorReadout(bool),
+    SetProjectorDetectEye(bool),
+    SetProjectorFps(String),
+    SetThrowX(String),
+    SetThrowZ(String),
+    SetThrowAngle(String),
+    SetThrowPixels(String),
+    ThrowPixelsFromEye,
+    AddThrow,
+    RemoveThrow(usize),
+    ClearThrows,
+    SetProjectorBackend(Backend),
+    SetBlitSource(usize, SourceKind),
+    SetBlitAnchor(usize, Anchor),
+    SetBlitOffset(usize, String),
+    SetBlitSize(usize, String),
+    SetBlitDestination(usize, String),
+    SetBlitScaling(usize, Scaling),
+    SetBlitFilter(usize, Filter),
+    AddBlit,
+    RemoveBlit(usize),
+    DetectNow,
+    Event(Event),
     Save,
 }
 
@@ -40,29 +308,84 @@ impl Window {
         let thin = old_config.thin.to_string();
         let tall = old_config.tall.to_string();
         let wide = old_config.wide.to_string();
+        let mouse_speeds = old_config.mouse.map(|mouse| {
+            mouse
+                .speed
+                .map(|speed| speed.to_string())
+                .unwrap_or_default()
+        });
 
-        let config = Arc::new(ArcSwap::from_pointee(old_config));
+        let projector_fps = old_config.projector_fps.to_string();
+
+        let config = Arc::new(ArcSwap::from_pointee(old_config.clone()));
 
         Self {
             old_config,
             config,
+            events: EventBus::default(),
+            instances: Vec::new(),
+            error: None,
             colors,
             thin,
             tall,
             wide,
+            mouse_speeds,
             changing: None,
+            detections: Vec::new(),
+            projector: 0,
+            projector_height: String::new(),
+            projector_position: String::new(),
+            projector_size: String::new(),
+            projector_monitor: String::new(),
+            projector_fps,
+            projector_stats: BTreeMap::new(),
+            eye: None,
+            blits: Vec::new(),
+            throw: ThrowText::default(),
+            throws: Vec::new(),
+            candidates: Vec::new(),
         }
+        .with_projector_texts()
     }
 
     fn update(&mut self, message: Message) {
         match message {
-            Message::KeyEvent(ev) => {
+            Message::Event(Event::InstanceAttached { instance, metadata }) => {
+                self.instances.push(AttachedInstance {
+                    instance,
+                    metadata,
+                    state: GameState::default(),
+                });
+            }
+            Message::Event(Event::InstanceDetached(instance)) => {
+                self.instances
+                    .retain(|attached| attached.instance != instance);
+            }
+            Message::Event(Event::GameStateChanged { instance, state }) => {
+                if let Some(attached) = self
+                    .instances
+                    .iter_mut()
+                    .find(|attached| attached.instance == instance)
+                {
+                    attached.state = state;
+                }
+            }
+            Message::Event(Event::ProjectorStats { name, stats }) => {
+                self.projector_stats.insert(name, stats);
+            }
+            Message::Event(Event::EyeDetected(eye)) => {
+                self.eye = eye;
+            }
+            Message::Event(Event::ErrorRaised(error)) => {
+                self.error = Some(error);
+            }
+            Message::Event(Event::KeyPressed(ev)) => {
                 let Some(changing) = self.changing else {
                     return;
                 };
-                let config = self.config.load_full().set_hotkey(
+                let config = Config::clone(&self.config.load_full()).set_key(
                     changing,
-                    Some(crate::keylogger::KeyFilter {
+                    Some(KeyFilter {
                         char: ev.char,
                         modifiers: Some(ev.modifiers),
                     }),
@@ -70,14 +393,45 @@ impl Window {
                 self.config.store(Arc::new(config));
                 self.changing = None;
             }
-            Message::Change(hotkey) => {
-                self.changing = Some(hotkey);
+            Message::Change(binding) => {
+                self.changing = Some(binding);
+            }
+            Message::SetHooksEnabled(hooks_enabled) => {
+                self.config.rcu(|config| Config {
+                    hooks_enabled,
+                    ..Config::clone(config)
+                });
+            }
+            Message::SetRequireInWorld(require_in_world) => {
+                self.config.rcu(|config| Config {
+                    require_in_world,
+                    ..Config::clone(config)
+                });
+            }
+            Message::SetResetOutsideWorld(reset_outside_world) => {
+                self.config.rcu(|config| Config {
+                    reset_outside_world,
+                    ..Config::clone(config)
+                });
+            }
+            Message::SetBorderless(borderless) => {
+                self.config.rcu(|config| Config {
+                    borderless,
+                    ..Config::clone(config)
+                });
+            }
+            Message::SetTransition(transition) => {
+                self.config.rcu(|config| Config {
+                    transition,
+                    ..Config::clone(config)
+                });
             }
             Message::SetResolution(hotkey, resolution) => {
                 match XY::from_str(&resolution) {
                     Ok(resolution) => {
-                        self.config
-                            .rcu(move |config| config.set_resolution(hotkey, resolution));
+                        self.config.rcu(move |config| {
+                            Config::clone(config).set_resolution(hotkey, resolution)
+                        });
                     }
                     Err(e) => {
                         println!("{:?}", e);
@@ -96,27 +450,754 @@ impl Window {
                     }
                 }
             }
+            Message::SetMouseSpeed(hotkey, speed) => {
+                if speed.is_empty() {
+                    self.config.rcu(move |config| {
+                        let mut config = Config::clone(config);
+                        config.mouse.get_mut(hotkey).speed = None;
+                        config
+                    });
+                } else {
+                    match speed.parse::<i32>() {
+                        Ok(speed) if (1..=20).contains(&speed) => {
+                            self.config.rcu(move |config| {
+                                let mut config = Config::clone(config);
+                                config.mouse.get_mut(hotkey).speed = Some(speed);
+                                config
+                            });
+                        }
+                        Ok(speed) => {
+                            self.error =
+                                Some(format!("Mouse speed must be from 1 to 20, not {}", speed));
+                        }
+                        Err(e) => {
+                            self.error = Some(format!("Invalid mouse speed: {}", e));
+                        }
+                    }
+                }
+
+                *self.mouse_speeds.get_mut(hotkey) = speed;
+            }
+            Message::SetPointerPrecision(hotkey, precision) => {
+                self.config.rcu(move |config| {
+                    let mut config = Config::clone(config);
+                    config.mouse.get_mut(hotkey).enhance_pointer_precision = precision.into();
+                    config
+                });
+            }
             Message::SetColor(i, color) => {
                 self.colors[i] = color;
 
                 if let Ok(color) = iced::Color::from_str(&self.colors[i]) {
                     self.config.rcu(|config| {
-                        let mut config = **config;
+                        let mut config = Config::clone(config);
                         config.colors[i] = config::color::Color::from(color);
                         config
                     });
                 }
             }
+            Message::SelectProjector(name) => {
+                if let Some(i) = self
+                    .config
+                    .load()
+                    .projectors
+                    .iter()
+                    .position(|projector| projector.name == name)
+                {
+                    self.projector = i;
+                    self.reload_projector();
+                }
+            }
+            Message::AddProjector => {
+                self.config.rcu(|config| {
+                    let mut config = Config::clone(config);
+                    let name = (1..)
+                        .map(|i| format!("projector {}", i))
+                        .find(|name| config.find_projector(name).is_none())
+                        .unwrap();
+                    config.projectors.push(ProjectorSettings::new(name));
+                    config
+                });
+
+                self.projector = self.config.load().projectors.len() - 1;
+                self.reload_projector();
+            }
+            Message::RemoveProjector => {
+                let i = self.projector;
+                self.config.rcu(|config| {
+                    let mut config = Config::clone(config);
+                    if i < config.projectors.len() {
+                        config.projectors.remove(i);
+                    }
+                    config
+                });
+
+                self.projector = 0;
+                self.reload_projector();
+            }
+            Message::SetProjectorPlacement(kind) => {
+                self.update_projector(|projector| {
+                    if PlacementKind::from(projector.placement) == kind {
+                        return;
+                    }
+
+                    let side = match projector.placement {
+                        Placement::Beside { side, .. } | Placement::Fill { side } => side,
+                        Placement::Custom { .. } => Side::default(),
+                    };
+                    projector.placement = match kind {
+                        PlacementKind::Beside => Placement::Beside { side, height: 800 },
+                        PlacementKind::Fill => Placement::Fill { side },
+                        PlacementKind::Custom => Placement::Custom {
+                            position: XY::new(0, 0),
+                            size: XY::new(400, 800),
+                        },
+                    };
+                });
+
+                self.reload_projector();
+            }
+            Message::SetProjectorSide(side) => {
+                self.update_projector(|projector| match &mut projector.placement {
+                    Placement::Beside { side: old, .. } | Placement::Fill { side: old } => {
+                        *old = side;
+                    }
+                    Placement::Custom { .. } => {}
+                });
+            }
+            Message::SetProjectorPosition(position) => {
+                if let Some(position) = parse_offset(&position) {
+                    self.update_projector(|projector| {
+                        if let Placement::Custom { position: old, .. } = &mut projector.placement {
+                            *old = position;
+                        }
+                    });
+                }
+
+                self.projector_position = position;
+            }
+            Message::SetProjectorSize(size) => {
+                if let Ok(size) = XY::from_str(&size) {
+                    self.update_projector(|projector| {
+                        if let Placement::Custom { size: old, .. } = &mut projector.placement {
+                            *old = size;
+                        }
+                    });
+                }
+
+                self.projector_size = size;
+            }
+            Message::SetProjectorMonitor(monitor) => {
+                if monitor.is_empty() {
+                    self.update_projector(|projector| projector.monitor = None);
+                } else if let Ok(i) = monitor.parse::<usize>() {
+                    self.update_projector(|projector| projector.monitor = Some(i));
+                }
+
+                self.projector_monitor = monitor;
+            }
+            Message::SetProjectorHeight(height) => {
+                if let Ok(height) = height.parse::<i32>() {
+                    self.update_projector(|projector| {
+                        if let Placement::Beside { height: old, .. } = &mut projector.placement {
+                            *old = height;
+                        }
+                    });
+                }
+
+                self.projector_height = height;
+            }
+            Message::SetProjectorShownIn(hotkey, shown) => {
+                self.update_projector(|projector| {
+                    projector.modes.retain(|&mode| mode != hotkey);
+                    if shown {
+                        projector.modes.push(hotkey);
+                    }
+                });
+            }
+            Message::SetProjectorTopmost(topmost) => {
+                self.update_projector(|projector| projector.topmost = topmost);
+            }
+            Message::SetProjectorRuler(ruler) => {
+                self.update_projector(|projector| projector.ruler = ruler);
+            }
+            Message::SetProjectorIsolation(enabled) => {
+                self.update_projector(|projector| projector.isolation.enabled = enabled);
+            }
+            Message::SetProjectorDetectEye(detect_eye) => {
+                self.update_projector(|projector| projector.detect_eye = detect_eye);
+            }
+            Message::SetProjectorReadout(enabled) => {
+                self.update_projector(|projector| {
+                    projector.readout = enabled.then_some(Destination::FULL);
+                });
+            }
+            Message::SetThrowX(x) => {
+                self.throw.x = x;
+            }
+            Message::SetThrowZ(z) => {
+                self.throw.z = z;
+            }
+            Message::SetThrowAngle(angle) => {
+                self.throw.angle = angle;
+            }
+            Message::SetThrowPixels(pixels) => {
+                self.throw.pixels = pixels;
+                self.throw.eye_correction = None;
+            }
+            Message::ThrowPixelsFromEye => {
+                if let Some(EyeReading { offset, correction }) = self.eye {
+                    self.throw.pixels = format!("{:.2}", offset.pixels);
+                    self.throw.eye_correction = Some(correction);
+                }
+            }
+            Message::AddThrow => {
+                let config = self.config.load();
+                // `inf` and `NaN` parse too, but would make every
+                // probability NaN.
+                let finite = |text: &str| {
+                    text.trim()
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|value| value.is_finite())
+                };
+                let pixels = match self.throw.pixels.trim() {
+                    "" => Some(None),
+                    pixels => finite(pixels).map(Some),
+                };
+
+                match (
+                    finite(&self.throw.x),
+                    finite(&self.throw.z),
+                    finite(&self.throw.angle),
+                    pixels,
+                ) {
+                    (Some(x), Some(z), Some(angle), Some(pixels)) => {
+                        let correction = pixels.map(|pixels| {
+                            self.throw.eye_correction.unwrap_or_else(|| {
+                                eye::angle_correction(pixels, config.tall.y, eye::DEFAULT_FOV)
+                            })
+                        });
+                        self.throws.push(Throw {
+                            x,
+                            z,
+                            angle,
+                            correction,
+                        });
+                        self.throw = ThrowText::default();
+                        self.candidates = calculator::triangulate(&self.throws, &config.calculator);
+                    }
+                    _ => {
+                        self.error = Some("Invalid throw".to_string());
+                    }
+                }
+            }
+            Message::RemoveThrow(i) => {
+                if i < self.throws.len() {
+                    self.throws.remove(i);
+                }
+                self.candidates =
+                    calculator::triangulate(&self.throws, &self.config.load().calculator);
+            }
+            Message::ClearThrows => {
+                self.throws.clear();
+                self.candidates.clear();
+            }
+            Message::SetProjectorFps(fps) => {
+                match fps.parse::<u32>() {
+                    Ok(projector_fps) if projector_fps > 0 => {
+                        self.config.rcu(|config| Config {
+                            projector_fps,
+                            ..Config::clone(config)
+                        });
+                    }
+                    Ok(_) => {
+                        self.error = Some("Projector FPS must be positive".to_string());
+                    }
+                    Err(e) => {
+                        self.error = Some(format!("Invalid projector FPS: {}", e));
+                    }
+                }
+
+                self.projector_fps = fps;
+            }
+            Message::SetBlitSource(i, kind) => {
+                self.update_blit(i, |blit| {
+                    blit.source = match kind {
+                        SourceKind::Rect => Source::Rect {
+                            anchor: Anchor::default(),
+                            offset: XY::new(0, 0),
+                            size: XY::new(100, 100),
+                        },
+                        SourceKind::Pie => Source::Pie,
+                        SourceKind::Entities => Source::Entities,
+                    };
+                });
+
+                self.reload_projector();
+            }
+            Message::SetBlitAnchor(i, anchor) => {
+                self.update_blit(i, |blit| {
+                    if let Source::Rect { anchor: old, .. } = &mut blit.source {
+                        *old = anchor;
+                    }
+                });
+            }
+            Message::SetBlitOffset(i, offset) => {
+                if let Some(offset) = parse_offset(&offset) {
+                    self.update_blit(i, |blit| {
+                        if let Source::Rect { offset: old, .. } = &mut blit.source {
+                            *old = offset;
+                        }
+                    });
+                }
+
+                self.blits[i].offset = offset;
+            }
+            Message::SetBlitSize(i, size) => {
+                if let Ok(size) = XY::from_str(&size) {
+                    self.update_blit(i, |blit| {
+                        if let Source::Rect { size: old, .. } = &mut blit.source {
+                            *old = size;
+                        }
+                    });
+                }
+
+                self.blits[i].size = size;
+            }
+            Message::SetBlitDestination(i, destination) => {
+                if let Some(destination) = parse_destination(&destination) {
+                    self.update_blit(i, |blit| blit.destination = destination);
+                }
+
+                self.blits[i].destination = destination;
+            }
+            Message::SetBlitScaling(i, scaling) => {
+                self.update_blit(i, |blit| blit.scaling = scaling);
+            }
+            Message::SetBlitFilter(i, filter) => {
+                self.update_blit(i, |blit| blit.filter = filter);
+            }
+            Message::SetProjectorBackend(projector_backend) => {
+                self.config.rcu(|config| Config {
+                    projector_backend,
+                    ..Config::clone(config)
+                });
+            }
+            Message::AddBlit => {
+                self.update_projector(|projector| {
+                    projector.blits.push(Blit {
+                        source: Source::Pie,
+                        destination: Destination::FULL,
+                        scaling: Scaling::default(),
+                        filter: Filter::default(),
+                    })
+                });
+
+                self.reload_projector();
+            }
+            Message::RemoveBlit(i) => {
+                self.update_projector(|projector| {
+                    if i < projector.blits.len() {
+                        projector.blits.remove(i);
+                    }
+                });
+
+                self.reload_projector();
+            }
+            Message::DetectNow => {
+                self.detections = instance::detect_windows(&self.config.load().detection);
+            }
+            Message::Event(_) => {}
             Message::Save => {
                 let config = self.config.load_full();
-                config.save_to_file().unwrap();
-                self.old_config = *config;
+                match config.save_to_file() {
+                    Ok(()) => {
+                        self.old_config = Config::clone(&config);
+                        self.error = None;
+                        self.events.publish(Event::ConfigReloaded);
+                    }
+                    Err(e) => {
+                        self.events
+                            .publish(Event::ErrorRaised(format!("Failed to save config: {}", e)));
+                    }
+                }
             }
         }
     }
 
+    /// Refreshes the text fields after switching projectors or changing
+    /// which blits there are.
+    fn reload_projector(&mut self) {
+        let config = self.config.load_full();
+        let projector = config.projectors.get(self.projector);
+
+        (
+            self.projector_height,
+            self.projector_position,
+            self.projector_size,
+        ) = match projector.map(|projector| projector.placement) {
+            Some(Placement::Beside { height, .. }) => {
+                (height.to_string(), String::new(), String::new())
+            }
+            Some(Placement::Custom { position, size }) => (
+                String::new(),
+                format!("{}, {}", position.x, position.y),
+                size.to_string(),
+            ),
+            _ => Default::default(),
+        };
+        self.projector_monitor = projector
+            .and_then(|projector| projector.monitor)
+            .map(|i| i.to_string())
+            .unwrap_or_default();
+        self.blits = BlitText::of_projector(projector);
+    }
+
+    fn with_projector_texts(mut self) -> Self {
+        self.reload_projector();
+        self
+    }
+
+    fn update_projector(&self, f: impl Fn(&mut ProjectorSettings)) {
+        let i = self.projector;
+        self.config.rcu(|config| {
+            let mut config = Config::clone(config);
+            if let Some(projector) = config.projectors.get_mut(i) {
+                f(projector);
+            }
+            config
+        });
+    }
+
+    fn update_blit(&self, i: usize, f: impl Fn(&mut Blit)) {
+        self.update_projector(|projector| {
+            if let Some(blit) = projector.blits.get_mut(i) {
+                f(blit);
+            }
+        });
+    }
+
+    fn projector_editor(&self, config: &Config) -> Element<'_, Message> {
+        let rendering = row![
+            text("Projector rendering").width(Length::Fill),
+            pick_list(
+                Backend::ALL,
+                Some(config.projector_backend),
+                Message::SetProjectorBackend
+            )
+            .width(100),
+            text_input("FPS", &self.projector_fps)
+                .width(50)
+                .on_input(Message::SetProjectorFps),
+            text("fps"),
+        ]
+        .spacing(6)
+        .align_y(Vertical::Center);
+
+        let projector = config.projectors.get(self.projector);
+
+        let header = row![
+            text("Projector").width(Length::Fill),
+            pick_list(
+                config
+                    .projectors
+                    .iter()
+                    .map(|projector| projector.name.clone())
+                    .collect::<Vec<_>>(),
+                projector.map(|projector| projector.name.clone()),
+                Message::SelectProjector
+            )
+            .width(150),
+            button(text!("Add").center())
+                .width(80)
+                .on_press(Message::AddProjector),
+            button(text!("Remove").center())
+                .width(80)
+                .on_press_maybe(projector.is_some().then_some(Message::RemoveProjector)),
+        ]
+        .spacing(6)
+        .align_y(Vertical::Center);
+
+        let Some(projector) = projector else {
+            return column![rendering, header].spacing(6).into();
+        };
+
+        let options = Row::with_children(Hotkey::ALL.into_iter().map(|hotkey| {
+            checkbox(projector.modes.contains(&hotkey))
+                .label(hotkey.to_string())
+                .on_toggle(move |shown| Message::SetProjectorShownIn(hotkey, shown))
+                .into()
+        }))
+        .push(
+            checkbox(projector.topmost)
+                .label("Topmost")
+                .on_toggle(Message::SetProjectorTopmost),
+        )
+        .push(
+            checkbox(projector.ruler)
+                .label("Ruler")
+                .on_toggle(Message::SetProjectorRuler),
+        )
+        .push(
+            checkbox(projector.isolation.enabled)
+                .label("Isolate")
+                .on_toggle(Message::SetProjectorIsolation),
+        )
+        .push(
+            checkbox(projector.readout.is_some())
+                .label("Pie readout")
+                .on_toggle(Message::SetProjectorReadout),
+        )
+        .push(
+            checkbox(projector.detect_eye)
+                .label("Detect eye")
+                .on_toggle(Message::SetProjectorDetectEye),
+        )
+        .spacing(8)
+        .align_y(Vertical::Center);
+
+        let mut placement = row![
+            text("Placement").width(Length::Fill),
+            pick_list(
+                PlacementKind::ALL,
+                Some(PlacementKind::from(projector.placement)),
+                Message::SetProjectorPlacement
+            )
+            .width(100),
+        ]
+        .spacing(6)
+        .align_y(Vertical::Center);
+
+        match projector.placement {
+            Placement::Beside { side, .. } | Placement::Fill { side } => {
+                placement = placement
+                    .push(pick_list(Side::ALL, Some(side), Message::SetProjectorSide).width(80));
+            }
+            Placement::Custom { .. } => {
+                placement = placement
+                    .push(
+                        text_input("x, y", &self.projector_position)
+                            .width(80)
+                            .on_input(Message::SetProjectorPosition),
+                    )
+                    .push(
+                        text_input("Size", &self.projector_size)
+                            .width(90)
+                            .on_input(Message::SetProjectorSize),
+                    );
+            }
+        }
+        if let Placement::Beside { .. } = projector.placement {
+            placement = placement.push(
+                text_input("Height", &self.projector_height)
+                    .width(60)
+                    .on_input(Message::SetProjectorHeight),
+            );
+        }
+        placement = placement.push(
+            text_input("Monitor", &self.projector_monitor)
+                .width(60)
+                .on_input(Message::SetProjectorMonitor),
+        );
+
+        let blits =
+            projector
+                .blits
+                .iter()
+                .zip(&self.blits)
+                .enumerate()
+                .map(|(i, (blit, texts))| {
+                    let mut source = row![
+                        pick_list(
+                            SourceKind::ALL,
+                            Some(SourceKind::from(blit.source)),
+                            move |kind| { Message::SetBlitSource(i, kind) }
+                        )
+                        .width(100)
+                    ]
+                    .spacing(6)
+                    .align_y(Vertical::Center);
+
+                    if let Source::Rect { anchor, .. } = blit.source {
+                        source = source
+                            .push(
+                                pick_list(Anchor::ALL, Some(anchor), move |anchor| {
+                                    Message::SetBlitAnchor(i, anchor)
+                                })
+                                .width(Length::Fill),
+                            )
+                            .push(
+                                text_input("Offset", &texts.offset)
+                                    .width(80)
+                                    .on_input(move |offset| Message::SetBlitOffset(i, offset)),
+                            )
+                            .push(
+                                text_input("Size", &texts.size)
+                                    .width(80)
+                                    .on_input(move |size| Message::SetBlitSize(i, size)),
+                            );
+                    }
+
+                    let destination = row![
+                        text_input("x, y, width, height", &texts.destination)
+                            .width(Length::Fill)
+                            .on_input(move |destination| Message::SetBlitDestination(
+                                i,
+                                destination
+                            )),
+                        pick_list(Scaling::ALL, Some(blit.scaling), move |scaling| {
+                            Message::SetBlitScaling(i, scaling)
+                        })
+                        .width(90),
+                        pick_list(Filter::ALL, Some(blit.filter), move |filter| {
+                            Message::SetBlitFilter(i, filter)
+                        })
+                        .width(90),
+                        button(text!("Remove").center())
+                            .width(80)
+                            .on_press(Message::RemoveBlit(i)),
+                    ]
+                    .spacing(6)
+                    .align_y(Vertical::Center);
+
+                    column![source, destination].spacing(4).into()
+                });
+
+        let stats = match self.projector_stats.get(&projector.name) {
+            Some(stats) if stats.frames > 0 => text!(
+                "{:.0} fps, a frame every {:.1} ms on average and {:.1} ms at worst, \
+                 painting takes {:.1} ms",
+                stats.fps(),
+                stats.interval.as_secs_f64() * 1000.0,
+                stats.worst_interval.as_secs_f64() * 1000.0,
+                stats.paint.as_secs_f64() * 1000.0
+            ),
+            _ => text!("Not shown"),
+        }
+        .size(12);
+
+        let eye = projector.detect_eye.then(|| {
+            match self.eye {
+                Some(EyeReading { offset, correction }) => text!(
+                    "Eye {:+.2} px from the crosshair, {:.0}% confident, {:+.4} degrees",
+                    offset.pixels,
+                    offset.confidence * 100.0,
+                    correction
+                ),
+                None => text!("No eye found"),
+            }
+            .size(12)
+        });
+
+        column![rendering, header, options, placement, stats,]
+            .push(eye)
+            .push(Column::with_children(blits).spacing(8))
+            .push(
+                button(text!("Add blit").center())
+                    .width(100)
+                    .on_press(Message::AddBlit),
+            )
+            .spacing(6)
+            .into()
+    }
+
+    fn calculator_panel(&self) -> Element<'_, Message> {
+        let input = row![
+            text_input("X", &self.throw.x)
+                .width(Length::Fill)
+                .on_input(Message::SetThrowX),
+            text_input("Z", &self.throw.z)
+                .width(Length::Fill)
+                .on_input(Message::SetThrowZ),
+            text_input("Angle", &self.throw.angle)
+                .width(Length::Fill)
+                .on_input(Message::SetThrowAngle),
+            text_input("Pixels", &self.throw.pixels)
+                .width(60)
+                .on_input(Message::SetThrowPixels),
+            button(text!("Eye").center())
+                .width(50)
+                .on_press_maybe(self.eye.is_some().then_some(Message::ThrowPixelsFromEye)),
+            button(text!("Add").center())
+                .width(60)
+                .on_press(Message::AddThrow),
+        ]
+        .spacing(6)
+        .align_y(Vertical::Center);
+
+        let throws = Column::with_children(self.throws.iter().enumerate().map(|(i, throw)| {
+            row![
+                text!(
+                    "{:.1}, {:.1} at {:.2}{}",
+                    throw.x,
+                    throw.z,
+                    throw.angle,
+                    throw
+                        .correction
+                        .map(|correction| format!(" {:+.4}", correction))
+                        .unwrap_or_default()
+                )
+                .size(12)
+                .width(Length::Fill),
+                button(text!("Remove").size(12).center())
+                    .width(70)
+                    .on_press(Message::RemoveThrow(i)),
+            ]
+            .spacing(6)
+            .align_y(Vertical::Center)
+            .into()
+        }))
+        .spacing(2);
+
+        let candidates = Column::with_children(self.candidates.iter().map(|candidate| {
+            let (x, z) = candidate.overworld();
+            let (nether_x, nether_z) = candidate.nether();
+            text!(
+                "{:5.1}%  {}, {}  nether {}, {}  {:.0} blocks",
+                candidate.probability * 100.0,
+                x,
+                z,
+                nether_x,
+                nether_z,
+                candidate.distance
+            )
+            .size(12)
+            .into()
+        }))
+        .spacing(2);
+
+        let header = row![
+            text("Stronghold").width(Length::Fill),
+            button(text!("Clear").center())
+                .width(80)
+                .on_press_maybe((!self.throws.is_empty()).then_some(Message::ClearThrows)),
+        ]
+        .spacing(6)
+        .align_y(Vertical::Center);
+
+        column![header, input, throws, candidates].spacing(6).into()
+    }
+
+    fn key_button(&self, config: &Config, binding: Binding) -> Element<'_, Message> {
+        button(
+            (if Some(binding) == self.changing {
+                text!("...")
+            } else {
+                config
+                    .get_key(binding)
+                    .map(|key| text!("{}", key))
+                    .unwrap_or(text!("Unset"))
+            })
+            .center(),
+        )
+        .width(Length::Fixed(100.0))
+        .on_press(Message::Change(binding))
+        .into()
+    }
+
     fn view(&self) -> Element<'_, Message> {
-        let config = *self.config.load_full();
+        let config = self.config.load_full();
 
         let hotkeys = Column::with_children(
             [
@@ -131,19 +1212,7 @@ impl Window {
                     text_input("Resolution", resolution)
                         .width(100)
                         .on_input(move |resolution| Message::SetResolution(hotkey, resolution)),
-                    button(
-                        (if Some(hotkey) == self.changing {
-                            text!("...")
-                        } else {
-                            config
-                                .get_hotkey(hotkey)
-                                .map(|hotkey| text!("{}", hotkey))
-                                .unwrap_or(text!("Unset"))
-                        })
-                        .center(),
-                    )
-                    .width(Length::Fixed(100.0))
-                    .on_press(Message::Change(hotkey))
+                    self.key_button(&config, Binding::Mode(hotkey)),
                 ]
                 .width(Length::Fill)
                 .spacing(6)
@@ -153,7 +1222,101 @@ impl Window {
         )
         .spacing(6);
 
+        let navigation = Column::with_children(
+            [("Back", Binding::Back), ("Reset", Binding::Reset)]
+                .into_iter()
+                .map(|(name, binding)| {
+                    row![
+                        text(name).width(Length::Fill),
+                        self.key_button(&config, binding)
+                    ]
+                    .spacing(6)
+                    .align_y(Vertical::Center)
+                    .into()
+                }),
+        )
+        .spacing(6);
+
+        let macros = Column::with_children(config.macros.iter().enumerate().map(|(i, m)| {
+            row![
+                text(m.name.clone()).width(Length::Fill),
+                self.key_button(&config, Binding::Macro(i))
+            ]
+            .spacing(6)
+            .align_y(Vertical::Center)
+            .into()
+        }))
+        .spacing(6);
+
+        let mouse = Column::with_children(
+            [
+                ("Tall mouse", Hotkey::Tall),
+                ("Thin mouse", Hotkey::Thin),
+                ("Wide mouse", Hotkey::Wide),
+            ]
+            .into_iter()
+            .map(|(name, hotkey)| {
+                row![
+                    text(name).width(Length::Fill),
+                    text_input("Speed", self.mouse_speeds.get(hotkey))
+                        .width(100)
+                        .on_input(move |speed| Message::SetMouseSpeed(hotkey, speed)),
+                    pick_list(
+                        Precision::ALL,
+                        Some(Precision::from(
+                            config.mouse.get(hotkey).enhance_pointer_precision
+                        )),
+                        move |precision| Message::SetPointerPrecision(hotkey, precision)
+                    )
+                    .width(100),
+                ]
+                .spacing(6)
+                .align_y(Vertical::Center)
+                .into()
+            }),
+        )
+        .spacing(6);
+
+        let transition = row![
+            text("Transition").width(Length::Fill),
+            pick_list(
+                Transition::ALL,
+                Some(config.transition),
+                Message::SetTransition
+            )
+            .width(100)
+        ]
+        .spacing(6)
+        .align_y(Vertical::Center);
+
+        let borderless = row![
+            text("Borderless").width(Length::Fill),
+            pick_list(
+                Borderless::ALL,
+                Some(config.borderless),
+                Message::SetBorderless
+            )
+            .width(100)
+        ]
+        .spacing(6)
+        .align_y(Vertical::Center);
+
+        let hooks = checkbox(config.hooks_enabled)
+            .label("Run mode hooks")
+            .on_toggle(Message::SetHooksEnabled);
+
+        let world = row![
+            checkbox(config.require_in_world)
+                .label("Only in world")
+                .on_toggle(Message::SetRequireInWorld),
+            checkbox(config.reset_outside_world)
+                .label("Reset outside world")
+                .on_toggle(Message::SetResetOutsideWorld),
+        ]
+        .spacing(12);
+
         let colors = Row::with_children((0..self.colors.len()).map(|i| {
+            let color = config.colors[i];
             row![
                 text_input("Color", &self.colors[i])
                     .width(Length::Fill)
@@ -162,7 +1325,7 @@ impl Window {
                     .height(Length::Fill)
                     .width(40)
                     .style(move |theme| container::Style {
-                        background: Some(Background::from(iced::Color::from(config.colors[i]))),
+                        background: Some(Background::from(iced::Color::from(color))),
                         ..container::rounded_box(theme)
                     })
             ]
@@ -172,28 +1335,98 @@ impl Window {
         .height(24)
         .spacing(12);
 
+        let detections = scrollable(
+            Column::with_children(self.detections.iter().map(|detection| {
+                text!(
+                    "{} {:?}: {}",
+                    if detection.verdict.is_match() {
+                        "[x]"
+                    } else {
+                        "[ ]"
+                    },
+                    detection.window.title,
+                    detection.verdict
+                )
+                .size(12)
+                .into()
+            }))
+            .spacing(2),
+        )
+        .height(if self.detections.is_empty() { 0 } else { 120 });
+
+        let projector = self.projector_editor(&config);
+        let calculator = self.calculator_panel();
+
+        let instances = Column::with_children(self.instances.iter().map(|attached| {
+            text!(
+                "{:?}: {} ({})",
+                attached.instance.hwnd,
+                attached.metadata,
+                attached.state
+            )
+            .into()
+        }));
+
+        let status = match (&self.error, self.instances.len()) {
+            (Some(error), _) => text!("{}", error),
+            (None, 0) => text!("No instance found"),
+            (None, 1) => text!("1 instance"),
+            (None, n) => text!("{} instances", n),
+        };
+
         let save = row![
-            space().width(Length::Fill),
+            status.width(Length::Fill),
+            button(text!("Detect now").center())
+                .width(100)
+                .on_press(Message::DetectNow),
             button(text!("Save").center())
                 .width(100)
-                .on_press_maybe((config != self.old_config).then_some(Message::Save))
+                .on_press_maybe((*config != self.old_config).then_some(Message::Save))
         ];
 
-        column![hotkeys, colors, space().height(Length::Fill), save]
-            .spacing(6)
-            .padding(16)
-            .into()
+        column![
+            hotkeys,
+            mouse,
+            navigation,
+            macros,
+            transition,
+            borderless,
+            hooks,
+            world,
+            colors,
+            projector,
+            calculator,
+            space().height(Length::Fill),
+            detections,
+            instances,
+            save
+        ]
+        .spacing(6)
+        .padding(16)
+        .into()
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        Subscription::run_with(Weak::into_raw(Arc::downgrade(&self.config)), |config| {
-            let config = unsafe { Weak::from_raw(*config).upgrade().unwrap() };
-            iced::stream::channel(100, async |tx| {
-                let mut manager = Manager::spawn(config).await;
-                manager.run(tx).await;
-            })
-        })
-        .map(|ev| Message::KeyEvent(ev))
+        Subscription::run_with(
+            (
+                Weak::into_raw(Arc::downgrade(&self.config)),
+                self.events.clone(),
+            ),
+            |(config, events)| {
+                let config = unsafe { Weak::from_raw(*config).upgrade().unwrap() };
+                let events = events.clone();
+                iced::stream::channel(100, async move |mut tx| {
+                    let mut rx = events.subscribe();
+                    let mut manager = Manager::spawn(config, events).await;
+                    futures::join!(manager.run(), async {
+                        while let Some(ev) = rx.next().await {
+                            let _ = tx.send(ev).await;
+                        }
+                    });
+                })
+            },
+        )
+        .map(Message::Event)
     }
 }
 
@@ -202,7 +1435,7 @@ pub fn spawn() {
         .subscription(Window::subscription)
         .window_size(Size {
             width: 480.0,
-            height: 360.0,
+            height: 960.0,
         })
         .run()
         .unwrap();